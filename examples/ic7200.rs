@@ -1,5 +1,6 @@
-use rigctld::{Daemon, Rig};
-use tokio::time::{sleep, Duration};
+use rigctld::{Daemon, Rig, RigHandle, RigWatcher};
+use tokio::time::Duration;
+use tokio_stream::StreamExt;
 
 #[tokio::main]
 async fn main() {
@@ -8,16 +9,19 @@ async fn main() {
         .set_serial_speed(19200)
         .set_civ_address(0x76)
         .set_rig_file("/dev/ttyUSB0".into());
-    let _rigctld = daemon.spawn().await.unwrap();
-
-    sleep(Duration::from_millis(1000)).await;
+    let mut rigctld = daemon.spawn().await.unwrap();
 
     let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
     rig.set_communication_timeout(Duration::from_millis(1000));
-    rig.connect().await.unwrap();
+    rig.connect_when_ready(Duration::from_secs(5), Some(&mut rigctld))
+        .await
+        .unwrap();
 
-    loop {
-        rig.get_frequency().await.unwrap();
-        rig.get_mode().await.unwrap();
+    let mut events = Box::pin(RigWatcher::new(RigHandle::spawn(rig)).spawn());
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(event) => println!("{:?}", event),
+            Err(e) => println!("Failed to poll the rig: {}", e),
+        }
     }
 }
@@ -0,0 +1,187 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::frequency::Frequency;
+use crate::rig::{Mode, Passband, Rig, RigError, Vfo};
+
+/// Reply to a command of a `Batch`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reply {
+    /// Reply to `get_vfo`
+    Vfo(Vfo),
+    /// Reply to `get_frequency`
+    Frequency(Frequency),
+    /// Reply to `get_mode`
+    Mode(Mode, Passband),
+    /// Reply to `get_ptt`
+    Ptt(bool),
+    /// Reply to `get_split_vfo`
+    Split(bool, Vfo),
+    /// Reply to `get_rf_power`
+    RfPower(f32),
+    /// Reply to `get_strength`
+    Strength(i32),
+    /// Reply to commands changing the state of the rig, e.g. `set_frequency`
+    Done,
+}
+
+/// Parser of the response to a command of a `Batch`.
+type Parser = Box<dyn Fn(&str) -> Result<Reply, RigError> + Send>;
+
+/// Command of a `Batch`.
+struct Command {
+    request: String,
+    parser: Parser,
+    /// Query whose cached reply is updated on success, e.g. `\get_freq` for `\set_freq`
+    cached: Option<(String, Option<Reply>)>,
+}
+
+/// Commands sent to `rigctld` at once, see `Rig::batch`.
+///
+/// The commands are written within a single write and their responses are read in order.
+/// Polling several values thus takes a single round trip instead of one per command.
+/// Queries are always sent to `rigctld`, their replies update the read cache (see `Rig::set_read_cache`).
+pub struct Batch<'a> {
+    rig: &'a mut Rig,
+    commands: Vec<Command>,
+}
+
+/// Batch implementation.
+impl<'a> Batch<'a> {
+    pub(crate) fn new(rig: &'a mut Rig) -> Batch<'a> {
+        Batch {
+            rig,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Queue a command together with the parser of its response.
+    fn push<F>(mut self, request: String, parser: F) -> Batch<'a>
+    where
+        F: Fn(&str) -> Result<Reply, RigError> + Send + 'static,
+    {
+        self.commands.push(Command {
+            request,
+            parser: Box::new(parser),
+            cached: None,
+        });
+        self
+    }
+
+    /// Queue a query whose reply is remembered by the read cache.
+    fn push_query<F>(self, request: String, parser: F) -> Batch<'a>
+    where
+        F: Fn(&str) -> Result<Reply, RigError> + Send + 'static,
+    {
+        let mut batch = self.push(request.clone(), parser);
+        if let Some(command) = batch.commands.last_mut() {
+            command.cached = Some((request, None));
+        }
+        batch
+    }
+
+    /// Queue a query of the currently selected VFO.
+    pub fn get_vfo(self) -> Batch<'a> {
+        self.push_query(Rig::get_vfo_request(), |r| {
+            Rig::parse_get_vfo(r).map(Reply::Vfo)
+        })
+    }
+
+    /// Queue a query of the rigs frequency.
+    pub fn get_frequency(self) -> Batch<'a> {
+        let command = format!(r";\get_freq{}", self.rig.vfo_arg());
+        self.push_query(command, |r| Rig::parse_get_freq(r).map(Reply::Frequency))
+    }
+
+    /// Queue a query of the rigs mode.
+    pub fn get_mode(self) -> Batch<'a> {
+        let command = format!(r";\get_mode{}", self.rig.vfo_arg());
+        self.push_query(command, |r| {
+            Rig::parse_get_mode(r).map(|(mode, passband)| Reply::Mode(mode, passband))
+        })
+    }
+
+    /// Queue a query whether the rig is transmitting.
+    pub fn get_ptt(self) -> Batch<'a> {
+        let command = format!(r";\get_ptt{}", self.rig.vfo_arg());
+        self.push_query(command, |r| Rig::parse_get_ptt(r).map(Reply::Ptt))
+    }
+
+    /// Queue a query of the split state.
+    pub fn get_split_vfo(self) -> Batch<'a> {
+        let command = format!(r";\get_split_vfo{}", self.rig.vfo_arg());
+        self.push_query(command, |r| {
+            Rig::parse_get_split_vfo(r).map(|(split, vfo)| Reply::Split(split, vfo))
+        })
+    }
+
+    /// Queue a query of the RF power level.
+    pub fn get_rf_power(self) -> Batch<'a> {
+        let command = format!(r";\get_level{} RFPOWER", self.rig.vfo_arg());
+        self.push_query(command, |r| Rig::parse_get_rf_power(r).map(Reply::RfPower))
+    }
+
+    /// Queue a query of the signal strength (S-meter).
+    pub fn get_strength(self) -> Batch<'a> {
+        let command = format!(r";\get_level{} STRENGTH", self.rig.vfo_arg());
+        self.push(command, |r| Rig::parse_get_strength(r).map(Reply::Strength))
+    }
+
+    /// Queue setting the rigs frequency.
+    pub fn set_frequency(self, frequency: Frequency) -> Batch<'a> {
+        let cached = format!(r";\get_freq{}", self.rig.vfo_arg());
+        let command = format!(r";\set_freq{} {}", self.rig.vfo_arg(), frequency.hz());
+
+        let mut batch = self.push(command, move |r| {
+            Rig::parse_set_freq(r, frequency).map(|()| Reply::Done)
+        });
+        if let Some(command) = batch.commands.last_mut() {
+            command.cached = Some((cached, Some(Reply::Frequency(frequency))));
+        }
+        batch
+    }
+
+    /// Get the number of queued commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Check whether no command is queued.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Send the queued commands and read their replies.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the replies in the order the commands were queued or in case of an error of the connection the error cause.
+    /// Each command fails on its own, e.g. if the rig does not support it.
+    pub async fn execute(self) -> Result<Vec<Result<Reply, RigError>>, RigError> {
+        let inputs: Vec<&str> = self.commands.iter().map(|c| c.request.as_str()).collect();
+        let responses = self.rig.execute_commands(&inputs).await?;
+
+        let mut replies = Vec::with_capacity(responses.len());
+        for (response, command) in responses.into_iter().zip(self.commands) {
+            let reply = response.and_then(|r| (command.parser)(&r));
+            match (&reply, command.cached) {
+                (Ok(reply), Some((request, None))) => self.rig.remember(request, *reply),
+                (Ok(_), Some((request, Some(cached)))) => {
+                    self.rig.forget(&request);
+                    self.rig.remember(request, cached);
+                }
+                // The value is unknown after a failed set command
+                (Err(_), Some((request, Some(_)))) => self.rig.forget(&request),
+                _ => (),
+            }
+            replies.push(reply);
+        }
+
+        Ok(replies)
+    }
+}
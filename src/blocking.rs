@@ -0,0 +1,534 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Blocking client API for synchronous applications.
+//!
+//! The types mirror their asynchronous counterparts, but each of them drives its own single threaded tokio runtime.
+//! They must not be used from within an asynchronous context, since blocking on a runtime within another one panics.
+
+use std::process::ExitStatus;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::io;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::watch;
+
+use crate::batch::{Batch, Reply};
+use crate::cat::CatTerminator;
+use crate::clock::Clock;
+use crate::conf::ConfToken;
+use crate::frequency::Frequency;
+use crate::keepalive::{Keepalive, LinkHealth};
+use crate::reconnect::{ConnectionStatus, ReconnectPolicy};
+use crate::rig::{Mode, Passband, RigError, ScanFunction, Vfo};
+use crate::throttle::Throttle;
+use crate::timeouts::Timeouts;
+use crate::transport::Transport;
+use crate::{daemon, rig};
+
+/// Create the runtime driving a blocking type.
+/// Panics if the runtime cannot be created, e.g. due to the process running out of file descriptors.
+fn runtime() -> Arc<Runtime> {
+    Arc::new(
+        Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime"),
+    )
+}
+
+/// Blocking representation of a connection to `rigctld`, see `rigctld::Rig`.
+pub struct Rig {
+    runtime: Arc<Runtime>,
+    rig: rig::Rig,
+}
+
+/// Generate a method blocking on the corresponding method of the asynchronous `Rig`.
+macro_rules! block_on {
+    ($(#[$doc:meta])* $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        $(#[$doc])*
+        pub fn $name(&mut self, $($arg: $ty),*) -> Result<$ret, RigError> {
+            self.runtime.block_on(self.rig.$name($($arg),*))
+        }
+    };
+}
+
+/// Rig implementation.
+impl Rig {
+    /// Create a new instance of `Rig`.
+    /// Panics if the underlying tokio runtime cannot be created.
+    pub fn new(host: &str, port: u16) -> Rig {
+        Rig {
+            runtime: runtime(),
+            rig: rig::Rig::new(host, port),
+        }
+    }
+
+    /// Create a new instance of `Rig` connecting to `rigctld` via the given transport, see `rigctld::Rig::with_transport`.
+    /// Panics if the underlying tokio runtime cannot be created.
+    pub fn with_transport<T: Transport + 'static>(transport: T) -> Rig {
+        Rig {
+            runtime: runtime(),
+            rig: rig::Rig::with_transport(transport),
+        }
+    }
+
+    /// Connect to `rigctld` as soon as it is ready to answer commands, see `rigctld::Rig::connect_when_ready`.
+    pub fn connect_when_ready(
+        &mut self,
+        timeout: Duration,
+        daemon: Option<&mut Rigctld>,
+    ) -> Result<(), RigError> {
+        let daemon = daemon.map(|d| &mut d.rigctld);
+        self.runtime
+            .block_on(self.rig.connect_when_ready(timeout, daemon))
+    }
+
+    /// Set the password to authenticate with on connect, see `rigctld::Rig::set_password`.
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.rig.set_password(password);
+    }
+
+    /// Disconnect from `rigctld`, see `rigctld::Rig::disconnect`.
+    pub fn disconnect(&mut self) -> bool {
+        self.rig.disconnect()
+    }
+
+    /// Set the policy to re-establish a lost connection, see `rigctld::Rig::set_reconnect_policy`.
+    pub fn set_reconnect_policy(&mut self, policy: Option<ReconnectPolicy>) {
+        self.rig.set_reconnect_policy(policy);
+    }
+
+    /// Get a receiver to follow the state of the connection, see `rigctld::Rig::subscribe_status`.
+    pub fn subscribe_status(&self) -> watch::Receiver<ConnectionStatus> {
+        self.rig.subscribe_status()
+    }
+
+    /// Set the keepalive settings, see `rigctld::Rig::set_keepalive`.
+    pub fn set_keepalive(&mut self, keepalive: Option<Keepalive>) {
+        self.rig.set_keepalive(keepalive);
+    }
+
+    /// Get a receiver to follow the health of the link, see `rigctld::Rig::subscribe_health`.
+    pub fn subscribe_health(&self) -> watch::Receiver<LinkHealth> {
+        self.rig.subscribe_health()
+    }
+
+    /// Get the round-trip time of the latest successful command.
+    pub fn get_latency(&self) -> Option<Duration> {
+        self.rig.get_latency()
+    }
+
+    /// Get the number of consecutive commands that failed due to a timeout or a lost connection.
+    pub fn get_consecutive_failures(&self) -> u32 {
+        self.rig.get_consecutive_failures()
+    }
+
+    /// Get the point in time the next keepalive is due, see `rigctld::Rig::keepalive_due`.
+    pub fn keepalive_due(&self) -> Option<Instant> {
+        self.rig.keepalive_due().map(|due| due.into_std())
+    }
+
+    /// Set communication timeout for communication with `rigctld`.
+    pub fn set_communication_timeout(&mut self, timeout: Duration) {
+        self.rig.set_communication_timeout(timeout);
+    }
+
+    /// Set the timeouts of the command classes and individual commands, see `rigctld::Rig::set_timeouts`.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.rig.set_timeouts(timeouts);
+    }
+
+    /// Get the timeouts of the command classes and individual commands.
+    pub fn get_timeouts(&self) -> &Timeouts {
+        self.rig.get_timeouts()
+    }
+
+    /// Override the timeout of all commands issued within the given closure, see `rigctld::Rig::with_timeout`,
+    /// e.g. `rig.with_timeout(d, |rig| rig.get_frequency())`.
+    pub fn with_timeout<F, T>(&mut self, timeout: Duration, commands: F) -> T
+    where
+        F: FnOnce(&mut Rig) -> T,
+    {
+        let previous = self.rig.replace_timeout_override(Some(timeout));
+        let result = commands(self);
+        self.rig.replace_timeout_override(previous);
+        result
+    }
+
+    /// Set the limits of the commands sent to `rigctld`, see `rigctld::Rig::set_throttle`.
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.rig.set_throttle(throttle);
+    }
+
+    /// Get the limits of the commands sent to `rigctld`.
+    pub fn get_throttle(&self) -> Option<&Throttle> {
+        self.rig.get_throttle()
+    }
+
+    /// Enable or disable the read cache, see `rigctld::Rig::set_read_cache`.
+    pub fn set_read_cache(&mut self, ttl: Option<Duration>) {
+        self.rig.set_read_cache(ttl);
+    }
+
+    /// Forget all values of the read cache.
+    pub fn clear_read_cache(&mut self) {
+        self.rig.clear_read_cache();
+    }
+
+    /// Check if connected to rig
+    pub fn is_connected(&self) -> bool {
+        self.rig.is_connected()
+    }
+
+    /// Send several commands to `rigctld` at once, see `rigctld::Rig::batch`.
+    /// The commands are queued by the given closure, e.g. `rig.batch(|b| b.get_frequency().get_mode())`.
+    pub fn batch<F>(&mut self, queue: F) -> Result<Vec<Result<Reply, RigError>>, RigError>
+    where
+        F: for<'a> FnOnce(Batch<'a>) -> Batch<'a>,
+    {
+        self.runtime.block_on(queue(self.rig.batch()).execute())
+    }
+
+    /// Set whether `rigctld` runs in VFO mode, see `rigctld::Rig::set_vfo_mode`.
+    pub fn set_vfo_mode(&mut self, enabled: bool) {
+        self.rig.set_vfo_mode(enabled);
+    }
+
+    /// Check if `rigctld` runs in VFO mode.
+    pub fn is_vfo_mode(&self) -> bool {
+        self.rig.is_vfo_mode()
+    }
+
+    /// Set the VFO addressed by VFO related commands in VFO mode, see `rigctld::Rig::set_target_vfo`.
+    pub fn set_target_vfo(&mut self, vfo: Option<Vfo>) {
+        self.rig.set_target_vfo(vfo);
+    }
+
+    /// Get the VFO addressed by VFO related commands in VFO mode.
+    pub fn get_target_vfo(&self) -> Option<Vfo> {
+        self.rig.get_target_vfo()
+    }
+
+    /// Target a VFO with all commands issued within the given closure, see `rigctld::Rig::with_vfo`,
+    /// e.g. `rig.with_vfo(Vfo::VFOB, |rig| rig.get_frequency())`.
+    pub fn with_vfo<F, T>(&mut self, vfo: Vfo, commands: F) -> T
+    where
+        F: FnOnce(&mut Rig) -> T,
+    {
+        let previous = self.rig.get_target_vfo();
+        self.rig.set_target_vfo(Some(vfo));
+        let result = commands(self);
+        self.rig.set_target_vfo(previous);
+        result
+    }
+
+    block_on!(
+        /// Connect to a already running `rigctld`, see `rigctld::Rig::connect`.
+        connect() -> ()
+    );
+    block_on!(
+        /// Connect to a already running `rigctld` that requires a password, see `rigctld::Rig::connect_with_password`.
+        connect_with_password(password: &str) -> ()
+    );
+    block_on!(
+        /// Send a keepalive to `rigctld`, see `rigctld::Rig::keepalive`.
+        keepalive() -> Duration
+    );
+    block_on!(
+        /// Check whether `rigctld` runs in VFO mode, see `rigctld::Rig::chk_vfo`.
+        chk_vfo() -> bool
+    );
+    block_on!(
+        /// Get the currently selected VFO, see `rigctld::Rig::get_vfo`.
+        get_vfo() -> Vfo
+    );
+    block_on!(
+        /// Get the rigs frequency, see `rigctld::Rig::get_frequency`.
+        get_frequency() -> Frequency
+    );
+    block_on!(
+        /// Set the rigs frequency, see `rigctld::Rig::set_frequency`.
+        set_frequency(frequency: Frequency) -> ()
+    );
+    block_on!(
+        /// Get the rigs tuning step, see `rigctld::Rig::get_ts`.
+        get_ts() -> u64
+    );
+    block_on!(
+        /// Set the rigs tuning step, see `rigctld::Rig::set_ts`.
+        set_ts(step: u64) -> ()
+    );
+    block_on!(
+        /// Tune the rig by a number of tuning steps, see `rigctld::Rig::step_frequency`.
+        step_frequency(steps: i64) -> Frequency
+    );
+    block_on!(
+        /// Tune the rig one tuning step up, see `rigctld::Rig::step_up`.
+        step_up() -> Frequency
+    );
+    block_on!(
+        /// Tune the rig one tuning step down, see `rigctld::Rig::step_down`.
+        step_down() -> Frequency
+    );
+    block_on!(
+        /// Move the rigs frequency onto the grid of the tuning step, see `rigctld::Rig::snap_frequency`.
+        snap_frequency() -> Frequency
+    );
+    block_on!(
+        /// Get the rigs mode, see `rigctld::Rig::get_mode`.
+        get_mode() -> (Mode, Passband)
+    );
+    block_on!(
+        /// Set the rigs mode, see `rigctld::Rig::set_mode`.
+        set_mode(mode: Mode, passband: Passband) -> ()
+    );
+    block_on!(
+        /// Check if the rig is transmitting, see `rigctld::Rig::get_ptt`.
+        get_ptt() -> bool
+    );
+    block_on!(
+        /// Switch the rig between transmitting and receiving, see `rigctld::Rig::set_ptt`.
+        set_ptt(ptt: bool) -> ()
+    );
+    block_on!(
+        /// Get the split state of the rig, see `rigctld::Rig::get_split_vfo`.
+        get_split_vfo() -> (bool, Vfo)
+    );
+    block_on!(
+        /// Control the rigs internal scan, see `rigctld::Rig::scan`.
+        scan(function: ScanFunction, channel: u32) -> ()
+    );
+    block_on!(
+        /// Get the rigs clock, see `rigctld::Rig::get_clock`.
+        get_clock() -> Clock
+    );
+    block_on!(
+        /// Set the rigs clock, see `rigctld::Rig::set_clock`.
+        set_clock(clock: &Clock) -> ()
+    );
+    block_on!(
+        /// Set the rigs clock to the time of the host system, see `rigctld::Rig::sync_clock`.
+        sync_clock(utc_offset: i16) -> Clock
+    );
+    block_on!(
+        /// Get the rigs RF power level, see `rigctld::Rig::get_rf_power`.
+        get_rf_power() -> f32
+    );
+    block_on!(
+        /// Set the rigs RF power level, see `rigctld::Rig::set_rf_power`.
+        set_rf_power(power: f32) -> ()
+    );
+    block_on!(
+        /// Convert a RF power level into milliwatts, see `rigctld::Rig::power_to_mw`.
+        power_to_mw(power: f32, frequency: Frequency, mode: &Mode) -> u32
+    );
+    block_on!(
+        /// Convert milliwatts into a RF power level, see `rigctld::Rig::mw_to_power`.
+        mw_to_power(mw: u32, frequency: Frequency, mode: &Mode) -> f32
+    );
+    block_on!(
+        /// Get the rigs RF power in watts, see `rigctld::Rig::get_rf_power_watts`.
+        get_rf_power_watts() -> f32
+    );
+    block_on!(
+        /// Set the rigs RF power in watts, see `rigctld::Rig::set_rf_power_watts`.
+        set_rf_power_watts(watts: f32) -> ()
+    );
+    block_on!(
+        /// Get the signal strength (S-meter) of the rig, see `rigctld::Rig::get_strength`.
+        get_strength() -> i32
+    );
+    block_on!(
+        /// Check if the front panel of the rig is locked, see `rigctld::Rig::get_lock_mode`.
+        get_lock_mode() -> bool
+    );
+    block_on!(
+        /// Lock or unlock the front panel of the rig, see `rigctld::Rig::set_lock_mode`.
+        set_lock_mode(locked: bool) -> ()
+    );
+    block_on!(
+        /// Get the twiddle timeout, see `rigctld::Rig::get_twiddle`.
+        get_twiddle() -> Duration
+    );
+    block_on!(
+        /// Set the twiddle timeout, see `rigctld::Rig::set_twiddle`.
+        set_twiddle(timeout: Duration) -> ()
+    );
+    block_on!(
+        /// Get the cache timeout of the rig backend, see `rigctld::Rig::get_cache`.
+        get_cache() -> Duration
+    );
+    block_on!(
+        /// Set the cache timeout of the rig backend, see `rigctld::Rig::set_cache`.
+        set_cache(timeout: Duration) -> ()
+    );
+    block_on!(
+        /// Get the value of a configuration token of the rig backend, see `rigctld::Rig::get_conf`.
+        get_conf(token: &str) -> String
+    );
+    block_on!(
+        /// Set the value of a configuration token of the rig backend, see `rigctld::Rig::set_conf`.
+        set_conf(token: &str, value: &str) -> ()
+    );
+    block_on!(
+        /// Get the configuration tokens of the rig backend, see `rigctld::Rig::dump_conf`.
+        dump_conf() -> Vec<ConfToken>
+    );
+    block_on!(
+        /// Send raw CAT bytes to the rig and read its reply, see `rigctld::Rig::send_raw_cat`.
+        send_raw_cat(bytes: &[u8], terminator: CatTerminator) -> Vec<u8>
+    );
+}
+
+/// Blocking representation of a spawned `rigctld`, see `rigctld::Rigctld`.
+pub struct Rigctld {
+    runtime: Arc<Runtime>,
+    rigctld: daemon::Rigctld,
+}
+
+/// Rigctld implementation.
+impl Rigctld {
+    /// Kill a running instance of `Rigctld`.
+    pub fn kill(&mut self) -> Result<(), io::Error> {
+        self.runtime.block_on(self.rigctld.kill())
+    }
+
+    /// Check if `Rigctld` is running.
+    pub fn is_running(&mut self) -> Result<bool, io::Error> {
+        self.rigctld.is_running()
+    }
+
+    /// Get the exit status of `Rigctld` if the process exited on its own.
+    pub fn exit_status(&mut self) -> Option<ExitStatus> {
+        self.rigctld.exit_status()
+    }
+}
+
+/// Blocking representation of `rigctld` commandline parameters, see `rigctld::Daemon`.
+pub struct Daemon {
+    runtime: Arc<Runtime>,
+    daemon: daemon::Daemon,
+}
+
+impl Default for Daemon {
+    /// Get default `rigctld` configuration, see `rigctld::Daemon::default`.
+    /// Panics if the underlying tokio runtime cannot be created.
+    fn default() -> Self {
+        Daemon {
+            runtime: runtime(),
+            daemon: daemon::Daemon::default(),
+        }
+    }
+}
+
+/// Generate a builder method forwarding to the asynchronous `Daemon`.
+macro_rules! builder {
+    ($(#[$doc:meta])* $name:ident($arg:ident: $ty:ty)) => {
+        $(#[$doc])*
+        pub fn $name(self, $arg: $ty) -> Daemon {
+            Daemon {
+                daemon: self.daemon.$name($arg),
+                ..self
+            }
+        }
+    };
+}
+
+/// Daemon implementation.
+impl Daemon {
+    /// Spawn new instance of `Rigctld`.
+    pub fn spawn(&self) -> Result<Rigctld, io::Error> {
+        let rigctld = self.runtime.block_on(self.daemon.spawn())?;
+
+        Ok(Rigctld {
+            runtime: self.runtime.clone(),
+            rigctld,
+        })
+    }
+
+    /// Get version of `rigctld`.
+    pub fn get_version(&self) -> Result<String, io::Error> {
+        self.runtime.block_on(self.daemon.get_version())
+    }
+
+    /// Get host for communication to daemon.
+    pub fn get_host(&self) -> &str {
+        self.daemon.get_host()
+    }
+
+    /// Get port for communication to daemon.
+    pub fn get_port(&self) -> u16 {
+        self.daemon.get_port()
+    }
+
+    /// Check if the VFO mode is enabled.
+    pub fn get_vfo_mode(&self) -> bool {
+        self.daemon.get_vfo_mode()
+    }
+
+    /// Get the multicast address the rig state is published on.
+    pub fn get_multicast_addr(&self) -> Option<&str> {
+        self.daemon.get_multicast_addr()
+    }
+
+    /// Get the port the rig state is published on.
+    pub fn get_multicast_port(&self) -> Option<u16> {
+        self.daemon.get_multicast_port()
+    }
+
+    builder!(
+        /// Sets the name of the `rigctld` program, see `rigctld::Daemon::set_program`.
+        set_program(app: String)
+    );
+    builder!(
+        /// Set the host or rather ip address to open the listening socket on.
+        set_host(host: String)
+    );
+    builder!(
+        /// Set the port to open the listening socket on.
+        set_port(port: u16)
+    );
+    builder!(
+        /// Set the device model. See `rigctld -l` for supported models.
+        set_model(model: u32)
+    );
+    builder!(
+        /// Set the rigs device file, e.g. `/dev/ttyUSB0`.
+        set_rig_file(file: String)
+    );
+    builder!(
+        /// Set the rigs serial speed, e.g. 19200.
+        set_serial_speed(speed: u32)
+    );
+    builder!(
+        /// Set the rigs CIV address, e.g. 0x76.
+        set_civ_address(addr: u16)
+    );
+    builder!(
+        /// Enable the VFO mode, see `rigctld::Daemon::set_vfo_mode`.
+        set_vfo_mode(enabled: bool)
+    );
+    builder!(
+        /// Set the password clients have to send before any other command, see `rigctld::Daemon::set_password`.
+        set_password(password: String)
+    );
+    builder!(
+        /// Set the multicast address `rigctld` publishes the rig state on, see `rigctld::Daemon::set_multicast_addr`.
+        set_multicast_addr(addr: String)
+    );
+    builder!(
+        /// Set the port `rigctld` publishes the rig state on.
+        set_multicast_port(port: u16)
+    );
+    builder!(
+        /// Set the multicast address `rigctld` receives commands on.
+        set_multicast_cmd_addr(addr: String)
+    );
+    builder!(
+        /// Set the port `rigctld` receives multicast commands on.
+        set_multicast_cmd_port(port: u16)
+    );
+}
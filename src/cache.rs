@@ -0,0 +1,136 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use tokio::time;
+
+use crate::batch::Reply;
+use crate::rig::Vfo;
+
+/// Replies to queries remembered for a limited time, see `Rig::set_read_cache`.
+///
+/// Replies are keyed by the request including its VFO argument, thus the values of different VFOs do not mix.
+#[derive(Debug, Clone)]
+pub(crate) struct ReadCache {
+    ttl: time::Duration,
+    entries: HashMap<String, (time::Instant, Reply)>,
+}
+
+/// ReadCache implementation.
+impl ReadCache {
+    /// Create an empty cache whose entries expire after the given time to live.
+    pub(crate) fn new(ttl: time::Duration) -> ReadCache {
+        ReadCache {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Get the reply to a request unless it is expired.
+    pub(crate) fn get(&self, request: &str) -> Option<Reply> {
+        self.entries
+            .get(request)
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, reply)| *reply)
+    }
+
+    /// Remember the reply to a request.
+    pub(crate) fn insert(&mut self, request: String, reply: Reply) {
+        self.entries.insert(request, (time::Instant::now(), reply));
+    }
+
+    /// Forget the reply to a request.
+    pub(crate) fn remove(&mut self, request: &str) {
+        self.entries.remove(request);
+    }
+
+    /// Forget the reply to a request of VFO mode along with the replies possibly referring to the same VFO.
+    /// A request for a specific VFO forgets the reply for `currVFO`, a request for `currVFO` forgets the replies for all VFOs.
+    pub(crate) fn remove_vfo(&mut self, request: &str) {
+        self.remove(request);
+        if let Some((command, vfo, rest)) = split_vfo(request) {
+            self.entries.retain(|other, _| match split_vfo(other) {
+                Some((c, v, r)) if c == command && r == rest => {
+                    vfo != Vfo::CurrVFO && v != Vfo::CurrVFO
+                }
+                _ => true,
+            });
+        }
+    }
+
+    /// Forget all replies.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Split a request of VFO mode into the command, the VFO and the remaining arguments,
+/// e.g. `;\get_level`, `VFOA` and `RFPOWER` for `;\get_level VFOA RFPOWER`.
+fn split_vfo(request: &str) -> Option<(&str, Vfo, &str)> {
+    let (command, args) = request.split_once(' ')?;
+    let (vfo, rest) = args.split_once(' ').unwrap_or((args, ""));
+    Some((command, Vfo::from_str(vfo).ok()?, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency::Frequency;
+
+    #[test]
+    fn entries() {
+        let mut cache = ReadCache::new(time::Duration::from_secs(60));
+        let reply = Reply::Frequency(Frequency::from_khz(7074));
+
+        cache.insert(String::from(r";\get_freq VFOA"), reply);
+        assert_eq!(cache.get(r";\get_freq VFOA"), Some(reply));
+        assert_eq!(cache.get(r";\get_freq VFOB"), None);
+
+        cache.remove(r";\get_freq VFOA");
+        assert_eq!(cache.get(r";\get_freq VFOA"), None);
+    }
+
+    #[test]
+    fn expiry() {
+        let mut cache = ReadCache::new(time::Duration::ZERO);
+
+        cache.insert(String::from(r";\get_ptt"), Reply::Ptt(true));
+        assert_eq!(cache.get(r";\get_ptt"), None);
+    }
+
+    #[test]
+    fn vfo_aliases() {
+        let mut cache = ReadCache::new(time::Duration::from_secs(60));
+        let reply = Reply::Frequency(Frequency::from_khz(7074));
+        let power = Reply::RfPower(0.5);
+
+        for request in [
+            r";\get_freq VFOA",
+            r";\get_freq VFOB",
+            r";\get_freq currVFO",
+        ] {
+            cache.insert(String::from(request), reply);
+        }
+        cache.insert(String::from(r";\get_level currVFO RFPOWER"), power);
+
+        // The current VFO might be the changed one
+        cache.remove_vfo(r";\get_freq VFOA");
+        assert_eq!(cache.get(r";\get_freq VFOA"), None);
+        assert_eq!(cache.get(r";\get_freq VFOB"), Some(reply));
+        assert_eq!(cache.get(r";\get_freq currVFO"), None);
+        assert_eq!(cache.get(r";\get_level currVFO RFPOWER"), Some(power));
+
+        // Any VFO might be the current one
+        cache.insert(String::from(r";\get_freq VFOA"), reply);
+        cache.insert(String::from(r";\get_level VFOA RFPOWER"), power);
+        cache.remove_vfo(r";\get_freq currVFO");
+        assert_eq!(cache.get(r";\get_freq VFOA"), None);
+        assert_eq!(cache.get(r";\get_freq VFOB"), None);
+        assert_eq!(cache.get(r";\get_level VFOA RFPOWER"), Some(power));
+        cache.remove_vfo(r";\get_level currVFO RFPOWER");
+        assert_eq!(cache.get(r";\get_level VFOA RFPOWER"), None);
+    }
+}
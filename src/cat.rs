@@ -0,0 +1,113 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::Write;
+
+use crate::rig::RigError;
+
+/// Determines how `rigctld` detects the end of a raw CAT reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatTerminator {
+    /// Use the end of message marker of the rig backend, e.g. `0xFD` for Icom rigs
+    Default,
+    /// Read until the given byte is received, e.g. `b';'` for Kenwood and Yaesu rigs
+    Byte(u8),
+    /// Read the given number of bytes
+    Length(usize),
+}
+
+/// Check whether raw CAT bytes form a printable ASCII command (e.g. `FA;`), which is sent and answered as text.
+pub(crate) fn is_text(bytes: &[u8]) -> bool {
+    !bytes.is_empty() && bytes.iter().all(|b| b.is_ascii_graphic() && *b != b'\\')
+}
+
+/// Encode raw CAT bytes as argument for `\send_cmd`.
+/// Printable ASCII commands (e.g. `FA;`) are sent as they are, all others as `\0xFE\0xFE...`.
+pub(crate) fn encode_command(bytes: &[u8]) -> String {
+    if is_text(bytes) {
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        bytes.iter().fold(String::new(), |mut s, b| {
+            let _ = write!(s, "\\0x{:02X}", b);
+            s
+        })
+    }
+}
+
+/// Encode a terminator as argument for `\send_cmd_rx`.
+pub(crate) fn encode_terminator(terminator: &CatTerminator) -> Option<String> {
+    match terminator {
+        CatTerminator::Default => None,
+        CatTerminator::Byte(b) if b.is_ascii_graphic() && *b != b'\\' => {
+            Some(char::from(*b).to_string())
+        }
+        CatTerminator::Byte(b) => Some(format!("\\0x{:02X}", b)),
+        CatTerminator::Length(n) => Some(n.to_string()),
+    }
+}
+
+/// Decode the reply of a rig as printed by `rigctld`.
+/// Replies to text commands are printed as they are (see `is_text`),
+/// replies to binary commands as space separated hex values (`0xfe 0xfe ...`).
+pub(crate) fn decode_reply(reply: &str, text: bool) -> Result<Vec<u8>, RigError> {
+    let reply = reply.trim_end_matches(['\n', ' ']);
+
+    if text {
+        return Ok(reply.as_bytes().to_vec());
+    }
+
+    reply
+        .split_whitespace()
+        .map(|t| {
+            t.strip_prefix("0x")
+                .or_else(|| t.strip_prefix("0X"))
+                .filter(|hex| hex.len() == 2)
+                .ok_or(RigError::ParseError)
+                .and_then(|hex| u8::from_str_radix(hex, 16).map_err(|_| RigError::ParseError))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        assert_eq!(encode_command(b"FA;"), "FA;");
+        assert_eq!(
+            encode_command(&[0xFE, 0xFE, 0x94, 0xE0, 0x03, 0xFD]),
+            r"\0xFE\0xFE\0x94\0xE0\0x03\0xFD"
+        );
+        assert_eq!(encode_command(b"A B"), r"\0x41\0x20\0x42");
+        assert_eq!(encode_terminator(&CatTerminator::Default), None);
+        assert_eq!(
+            encode_terminator(&CatTerminator::Byte(b';')),
+            Some(";".into())
+        );
+        assert_eq!(
+            encode_terminator(&CatTerminator::Byte(0xFD)),
+            Some(r"\0xFD".into())
+        );
+        assert_eq!(
+            encode_terminator(&CatTerminator::Length(11)),
+            Some("11".into())
+        );
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(
+            decode_reply("0xfe 0xfe 0xe0 0x94 0xfb 0xfd \n", false),
+            Ok(vec![0xFE, 0xFE, 0xE0, 0x94, 0xFB, 0xFD])
+        );
+        assert_eq!(
+            decode_reply("FA00014074000;", true),
+            Ok(b"FA00014074000;".to_vec())
+        );
+        assert_eq!(decode_reply("0xfd", true), Ok(b"0xfd".to_vec()));
+        assert!(decode_reply("0xzz 0xfd", false).is_err());
+        assert!(decode_reply("FA;", false).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::rig::RigError;
+
+/// Date and time of the rigs clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+    /// Offset of the local time to UTC (minutes)
+    pub utc_offset: i16,
+}
+
+/// Clock implementation.
+impl Clock {
+    /// Get the current UTC time of the host system.
+    pub fn now() -> Clock {
+        Clock::from_system_time(SystemTime::now())
+    }
+
+    /// Convert a `SystemTime` into UTC date and time.
+    pub fn from_system_time(time: SystemTime) -> Clock {
+        let millis = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_millis() as i64,
+            Err(e) => -(e.duration().as_millis() as i64),
+        };
+
+        let days = millis.div_euclid(86_400_000);
+        let millis_of_day = millis.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days);
+
+        Clock {
+            year: year as u16,
+            month,
+            day,
+            hour: (millis_of_day / 3_600_000) as u8,
+            minute: (millis_of_day / 60_000 % 60) as u8,
+            second: (millis_of_day / 1_000 % 60) as u8,
+            millisecond: (millis_of_day % 1_000) as u16,
+            utc_offset: 0,
+        }
+    }
+
+    /// Convert the date and time into a `SystemTime`, taking the UTC offset into account.
+    pub fn to_system_time(&self) -> SystemTime {
+        let days = days_from_civil(i64::from(self.year), self.month, self.day);
+        let millis = days * 86_400_000
+            + i64::from(self.hour) * 3_600_000
+            + i64::from(self.minute) * 60_000
+            + i64::from(self.second) * 1_000
+            + i64::from(self.millisecond)
+            - i64::from(self.utc_offset) * 60_000;
+
+        if millis >= 0 {
+            UNIX_EPOCH + Duration::from_millis(millis as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_millis(millis.unsigned_abs())
+        }
+    }
+
+    /// Get the same point in time expressed as local time with the given UTC offset (minutes).
+    pub fn with_utc_offset(&self, utc_offset: i16) -> Clock {
+        let offset = Duration::from_secs(u64::from(utc_offset.unsigned_abs()) * 60);
+        let shifted = if utc_offset >= 0 {
+            self.to_system_time() + offset
+        } else {
+            self.to_system_time() - offset
+        };
+
+        Clock {
+            utc_offset,
+            ..Clock::from_system_time(shifted)
+        }
+    }
+}
+
+impl fmt::Display for Clock {
+    /// Format the clock as used by `rigctld`, e.g. `2023-08-15T12:34:56.000+0200`.
+    /// The UTC offset is given as `±HHMM`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}{}{:02}{:02}",
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            self.millisecond,
+            if self.utc_offset < 0 { '-' } else { '+' },
+            self.utc_offset.unsigned_abs() / 60,
+            self.utc_offset.unsigned_abs() % 60
+        )
+    }
+}
+
+impl FromStr for Clock {
+    type Err = RigError;
+
+    /// Parse a clock as reported by `rigctld`, e.g. `2023-08-15T12:34:56.000+02`.
+    /// The UTC offset may be given as `±HH`, `±HHMM` or `±HH:MM` and defaults to UTC if missing.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(
+                r"^(\d{4})-(\d{2})-(\d{2})T(\d{2}):(\d{2}):(\d{2})(?:\.(\d{1,3})\d*)?(?:([+-])(\d{2}):?(\d{2})?)?$"
+            )
+            .unwrap();
+        }
+
+        let c = RE.captures(s.trim()).ok_or(RigError::ParseError)?;
+        let number = |i: usize| c.get(i).map_or(0, |m| m.as_str().parse::<u16>().unwrap());
+
+        let millisecond = c.get(7).map_or(0, |m| {
+            let digits = m.as_str();
+            digits.parse::<u16>().unwrap() * 10u16.pow(3 - digits.len() as u32)
+        });
+        let utc_offset = (number(9) * 60 + number(10)) as i16;
+        let utc_offset = match c.get(8).map(|m| m.as_str()) {
+            Some("-") => -utc_offset,
+            _ => utc_offset,
+        };
+
+        let clock = Clock {
+            year: number(1),
+            month: number(2) as u8,
+            day: number(3) as u8,
+            hour: number(4) as u8,
+            minute: number(5) as u8,
+            second: number(6) as u8,
+            millisecond,
+            utc_offset,
+        };
+
+        if (1..=12).contains(&clock.month)
+            && (1..=31).contains(&clock.day)
+            && clock.hour < 24
+            && clock.minute < 60
+            && clock.second < 61
+        {
+            Ok(clock)
+        } else {
+            Err(RigError::ParseError)
+        }
+    }
+}
+
+/// Convert days since 1970-01-01 into a civil date (year, month, day).
+/// See <http://howardhinnant.github.io/date_algorithms.html>.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Convert a civil date into days since 1970-01-01.
+/// See <http://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let clock = Clock::from_str("2023-08-15T12:34:56.789-0530").unwrap();
+        assert_eq!(
+            clock,
+            Clock {
+                year: 2023,
+                month: 8,
+                day: 15,
+                hour: 12,
+                minute: 34,
+                second: 56,
+                millisecond: 789,
+                utc_offset: -330,
+            }
+        );
+        let clock = Clock::from_str("2023-08-15T12:34:56.5+02").unwrap();
+        assert_eq!((clock.millisecond, clock.utc_offset), (500, 120));
+        let clock = Clock::from_str("2023-08-15T12:34:56").unwrap();
+        assert_eq!((clock.millisecond, clock.utc_offset), (0, 0));
+        assert!(Clock::from_str("2023-13-15T12:34:56").is_err());
+        assert!(Clock::from_str("15.08.2023 12:34").is_err());
+    }
+
+    #[test]
+    fn format_roundtrip() {
+        let clock = Clock::from_str("2024-02-29T23:59:59.001+0100").unwrap();
+        assert_eq!(clock.to_string(), "2024-02-29T23:59:59.001+0100");
+        assert_eq!(Clock::from_str(&clock.to_string()), Ok(clock));
+    }
+
+    #[test]
+    fn system_time() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_199_001);
+        let clock = Clock::from_system_time(time);
+        assert_eq!(clock.to_string(), "2024-02-29T23:59:59.001+0000");
+        assert_eq!(clock.to_system_time(), time);
+
+        let local = clock.with_utc_offset(60);
+        assert_eq!(local.to_string(), "2024-03-01T00:59:59.001+0100");
+        assert_eq!(local.to_system_time(), time);
+    }
+}
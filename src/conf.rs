@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::rig::RigError;
+
+/// Range of values accepted by a configuration token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfRange {
+    /// Numeric value within `min..=max`
+    Numeric { min: f64, max: f64, step: f64 },
+    /// Boolean value, either 0 or 1
+    Checkbox,
+    /// One of the listed values
+    Combo(Vec<String>),
+    /// Any value, e.g. a string or a device path
+    Any,
+}
+
+/// Configuration token of a rig backend as listed by `\dump_conf`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfToken {
+    /// Name of the token as used with `set_conf` and `get_conf`, e.g. `write_delay`
+    pub name: String,
+    /// Human readable description of the token
+    pub description: String,
+    /// Default value of the token
+    pub default: String,
+    /// Current value of the token
+    pub value: String,
+    /// Range of accepted values
+    pub range: ConfRange,
+}
+
+/// Parse the output of `\dump_conf`.
+///
+/// Each token is described by two or three lines:
+///
+/// ```text
+/// write_delay: "Delay in ms between each byte sent out"
+///         Default: 0, Value: 0
+///         Range: 0.0..1000.0, step 1.0
+/// ```
+pub(crate) fn parse_dump_conf(response: &str) -> Result<Vec<ConfToken>, RigError> {
+    lazy_static! {
+        static ref RE_NAME: Regex = Regex::new(r#"^(\S+): "(.*)"$"#).unwrap();
+        static ref RE_VALUE: Regex = Regex::new(r"^Default: (.*), Value: (.*)$").unwrap();
+        static ref RE_RANGE: Regex =
+            Regex::new(r"^Range: (-?[0-9.]+)\.\.(-?[0-9.]+), step (-?[0-9.]+)$").unwrap();
+    }
+
+    let mut tokens: Vec<ConfToken> = Vec::new();
+
+    let lines = response
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with("dump_conf:") && !l.starts_with("RPRT"));
+
+    for line in lines {
+        if let Some(c) = RE_NAME.captures(line) {
+            tokens.push(ConfToken {
+                name: c[1].to_string(),
+                description: c[2].to_string(),
+                default: String::new(),
+                value: String::new(),
+                range: ConfRange::Any,
+            });
+            continue;
+        }
+
+        let token = tokens.last_mut().ok_or(RigError::ParseError)?;
+
+        if let Some(c) = RE_VALUE.captures(line) {
+            token.default = c[1].to_string();
+            token.value = c[2].to_string();
+        } else if let Some(c) = RE_RANGE.captures(line) {
+            let number = |i: usize| c[i].parse::<f64>().map_err(|_| RigError::ParseError);
+            token.range = ConfRange::Numeric {
+                min: number(1)?,
+                max: number(2)?,
+                step: number(3)?,
+            };
+        } else if line.starts_with("Checkbox:") {
+            token.range = ConfRange::Checkbox;
+        } else if let Some(combo) = line.strip_prefix("Combo:") {
+            token.range =
+                ConfRange::Combo(combo.split(',').map(|s| s.trim().to_string()).collect());
+        } else {
+            return Err(RigError::ParseError);
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_conf() {
+        let response = "dump_conf:;\n\
+            rig_pathname: \"Path name to the device file of the rig\"\n\
+            \tDefault: /dev/rig, Value: /dev/ttyUSB0\n\
+            \n\
+            write_delay: \"Delay in ms between each byte sent out\"\n\
+            \tDefault: 0, Value: 0\n\
+            \tRange: 0.0..1000.0, step 1.0\n\
+            rts_state: \"Serial port set state of RTS signal for external powering\"\n\
+            \tDefault: Unset, Value: Unset\n\
+            \tCombo: Unset, ON, OFF\n\
+            auto_power_on: \"True enables compatible rigs to be powered up on open\"\n\
+            \tDefault: 0, Value: 0\n\
+            \tCheckbox: 0,1\n\
+            RPRT 0";
+
+        let tokens = parse_dump_conf(response).unwrap();
+
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[0].name, "rig_pathname");
+        assert_eq!(tokens[0].value, "/dev/ttyUSB0");
+        assert_eq!(tokens[0].range, ConfRange::Any);
+        assert_eq!(
+            tokens[1].description,
+            "Delay in ms between each byte sent out"
+        );
+        assert_eq!(
+            tokens[1].range,
+            ConfRange::Numeric {
+                min: 0.0,
+                max: 1000.0,
+                step: 1.0
+            }
+        );
+        assert_eq!(
+            tokens[2].range,
+            ConfRange::Combo(vec!["Unset".into(), "ON".into(), "OFF".into()])
+        );
+        assert_eq!(tokens[3].range, ConfRange::Checkbox);
+    }
+
+    #[test]
+    fn dump_conf_invalid() {
+        assert!(parse_dump_conf("dump_conf:;\n\tDefault: 0, Value: 0\nRPRT 0").is_err());
+    }
+}
@@ -2,8 +2,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fmt;
 use std::io::Read;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 
 use tokio::io;
 use tokio::process::{Child, Command};
@@ -11,6 +12,7 @@ use tokio::process::{Child, Command};
 /// Representation of hamlib's `rigctld`.
 pub struct Rigctld {
     daemon: Option<Child>,
+    status: Option<ExitStatus>,
 }
 
 /// Rigctld implementation.
@@ -19,6 +21,7 @@ impl Rigctld {
     pub fn new(child: Child) -> Self {
         Self {
             daemon: Some(child),
+            status: None,
         }
     }
 
@@ -42,8 +45,9 @@ impl Rigctld {
     pub fn is_running(&mut self) -> Result<bool, io::Error> {
         if let Some(d) = self.daemon.as_mut() {
             match d.try_wait()? {
-                Some(_) => {
+                Some(status) => {
                     self.daemon = None;
+                    self.status = Some(status);
                     Ok(false)
                 }
                 None => Ok(true),
@@ -55,10 +59,15 @@ impl Rigctld {
             ))
         }
     }
+
+    /// Get the exit status of `Rigctld` if the process exited on its own, e.g. because the port was already taken.
+    pub fn exit_status(&mut self) -> Option<ExitStatus> {
+        let _ = self.is_running();
+        self.status
+    }
 }
 
 /// Representation of `rigctld` commandline parameters.
-#[derive(Debug)]
 pub struct Daemon {
     program: String,
     host: String,
@@ -67,6 +76,33 @@ pub struct Daemon {
     rig_file: Option<String>,
     serial_speed: Option<u32>,
     civ_address: Option<u16>,
+    vfo_mode: bool,
+    password: Option<String>,
+    multicast_addr: Option<String>,
+    multicast_port: Option<u16>,
+    multicast_cmd_addr: Option<String>,
+    multicast_cmd_port: Option<u16>,
+}
+
+impl fmt::Debug for Daemon {
+    /// Format the configuration without revealing the password.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Daemon")
+            .field("program", &self.program)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("model", &self.model)
+            .field("rig_file", &self.rig_file)
+            .field("serial_speed", &self.serial_speed)
+            .field("civ_address", &self.civ_address)
+            .field("vfo_mode", &self.vfo_mode)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("multicast_addr", &self.multicast_addr)
+            .field("multicast_port", &self.multicast_port)
+            .field("multicast_cmd_addr", &self.multicast_cmd_addr)
+            .field("multicast_cmd_port", &self.multicast_cmd_port)
+            .finish()
+    }
 }
 
 impl Default for Daemon {
@@ -81,6 +117,12 @@ impl Default for Daemon {
             rig_file: None,
             serial_speed: None,
             civ_address: None,
+            vfo_mode: false,
+            password: None,
+            multicast_addr: None,
+            multicast_port: None,
+            multicast_cmd_addr: None,
+            multicast_cmd_port: None,
         }
     }
 }
@@ -105,6 +147,24 @@ impl Daemon {
         if let Some(civ) = self.civ_address.as_ref() {
             cmd.args(["-c", &civ.to_string()]);
         }
+        if self.vfo_mode {
+            cmd.arg("-o");
+        }
+        if let Some(password) = self.password.as_ref() {
+            cmd.args(["-A", password]);
+        }
+        if let Some(addr) = self.multicast_addr.as_ref() {
+            cmd.args(["-M", addr]);
+        }
+        if let Some(port) = self.multicast_port.as_ref() {
+            cmd.arg(format!("--multicast-port={}", port));
+        }
+        if let Some(addr) = self.multicast_cmd_addr.as_ref() {
+            cmd.arg(format!("--multicast-cmd-addr={}", addr));
+        }
+        if let Some(port) = self.multicast_cmd_port.as_ref() {
+            cmd.arg(format!("--multicast-cmd-port={}", port));
+        }
 
         let daemon = Rigctld::new(cmd.spawn()?);
 
@@ -180,6 +240,59 @@ impl Daemon {
         self.civ_address = Some(addr);
         self
     }
+
+    /// Enable the VFO mode (`--vfo`), in which every VFO related command requires the target VFO as argument.
+    pub fn set_vfo_mode(mut self, enabled: bool) -> Daemon {
+        self.vfo_mode = enabled;
+        self
+    }
+
+    /// Check if the VFO mode is enabled.
+    pub fn get_vfo_mode(&self) -> bool {
+        self.vfo_mode
+    }
+
+    /// Set the password clients have to send before any other command (`--password`).
+    /// Requires hamlib 4.6 or newer.
+    pub fn set_password(mut self, password: String) -> Daemon {
+        self.password = Some(password);
+        self
+    }
+
+    /// Set the multicast address `rigctld` publishes the rig state on, e.g. `224.0.0.1` (`--multicast-addr`).
+    /// Requires hamlib 4.6 or newer.
+    pub fn set_multicast_addr(mut self, addr: String) -> Daemon {
+        self.multicast_addr = Some(addr);
+        self
+    }
+
+    /// Get the multicast address the rig state is published on.
+    pub fn get_multicast_addr(&self) -> Option<&str> {
+        self.multicast_addr.as_deref()
+    }
+
+    /// Set the port `rigctld` publishes the rig state on (`--multicast-port`).
+    pub fn set_multicast_port(mut self, port: u16) -> Daemon {
+        self.multicast_port = Some(port);
+        self
+    }
+
+    /// Get the port the rig state is published on.
+    pub fn get_multicast_port(&self) -> Option<u16> {
+        self.multicast_port
+    }
+
+    /// Set the multicast address `rigctld` receives commands on (`--multicast-cmd-addr`).
+    pub fn set_multicast_cmd_addr(mut self, addr: String) -> Daemon {
+        self.multicast_cmd_addr = Some(addr);
+        self
+    }
+
+    /// Set the port `rigctld` receives multicast commands on (`--multicast-cmd-port`).
+    pub fn set_multicast_cmd_port(mut self, port: u16) -> Daemon {
+        self.multicast_cmd_port = Some(port);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -211,17 +324,25 @@ mod tests {
     fn daemon_lifecycle() {
         tokio!({
             let mut d = Daemon::default().spawn().await.unwrap();
-            assert_eq!(d.is_running().unwrap(), true);
+            assert!(d.is_running().unwrap());
             d.kill().await.unwrap();
         })
     }
 
+    #[test]
+    fn daemon_debug() {
+        let daemon = Daemon::default().set_password("secret".into());
+        let debug = format!("{:?}", daemon);
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("<redacted>"));
+    }
+
     #[test]
     fn daemon_kill_twice() {
         tokio!({
             let mut d = Daemon::default().spawn().await.unwrap();
             d.kill().await.unwrap();
-            assert_eq!(d.kill().await.is_err(), true);
+            assert!(d.kill().await.is_err());
         })
     }
 }
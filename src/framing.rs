@@ -0,0 +1,220 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+
+/// Get the name of a command without prefix and arguments, e.g. `get_freq` for `;\get_freq VFOA`.
+pub(crate) fn command_name(command: &str) -> &str {
+    let command = command.trim_start_matches([';', '+', '\\']);
+    command.split_whitespace().next().unwrap_or(command)
+}
+
+/// Check if a command only queries the rig, e.g. `get_freq` or `;\dump_caps`.
+/// Queries may be repeated safely and are executed with normal priority by `RigHandle`.
+pub(crate) fn is_query(command: &str) -> bool {
+    let name = command_name(command);
+    name.starts_with("get_")
+        || name.starts_with("dump_")
+        || ["chk_vfo", "power2mW", "mW2power"].contains(&name)
+}
+
+/// Get the arguments of a command separated by single spaces, e.g. `VFOA RFPOWER` for `;\get_level VFOA RFPOWER`.
+fn command_args(command: &str) -> String {
+    let command = command.trim_start_matches([';', '+', '\\']);
+    command
+        .split_whitespace()
+        .skip(1)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Get the command name and arguments `rigctld` echoes at the beginning of a response,
+/// e.g. `get_level` and `RFPOWER` for `get_level: RFPOWER;Level Value: 0.5;RPRT 0`.
+/// Plain responses like `RPRT -1` or `CHKVFO 1` do not carry a name.
+fn echoed(response: &str) -> Option<(&str, String)> {
+    let (name, rest) = response.split_once(':')?;
+    let valid = !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    let args = rest.split([';', '\n']).next().unwrap_or_default();
+    valid.then(|| (name, args.split_whitespace().collect::<Vec<_>>().join(" ")))
+}
+
+/// Request sent to `rigctld`.
+#[derive(Debug)]
+struct Request {
+    name: String,
+    args: String,
+    /// Number of the write the request was sent with
+    write: u64,
+}
+
+/// Requests sent to `rigctld` whose responses have not been read yet.
+///
+/// A request stays outstanding if the future executing it is dropped or times out before its response is read.
+/// `rigctld` answers the requests of a connection in order, so the response of such a request precedes the responses of later requests.
+/// Responses are correlated by the echoed command name and arguments, which allows to resynchronise if a response never arrives:
+/// A response is attributed to the oldest request of its name (and arguments, if they match) within the latest write,
+/// otherwise to the newest one of the earlier writes. Older requests are considered to be lost.
+/// Responses whose name matches none of the outstanding requests arrived after their request was considered lost and are stale.
+#[derive(Debug, Default)]
+pub(crate) struct Requests {
+    outstanding: VecDeque<Request>,
+    writes: u64,
+}
+
+/// Requests implementation.
+impl Requests {
+    /// Record the requests of a write before they are sent.
+    pub(crate) fn push(&mut self, commands: &[&str]) {
+        self.writes += 1;
+        for command in commands {
+            self.outstanding.push_back(Request {
+                name: String::from(command_name(command)),
+                args: command_args(command),
+                write: self.writes,
+            });
+        }
+    }
+
+    /// Find the request a response of the given command is attributed to.
+    fn position<F>(&self, matches: F) -> Option<usize>
+    where
+        F: Fn(&Request) -> bool,
+    {
+        // The requests of a write are answered in order
+        self.outstanding
+            .iter()
+            .position(|r| r.write == self.writes && matches(r))
+            .or_else(|| self.outstanding.iter().rposition(matches))
+    }
+
+    /// Match a response to the outstanding requests.
+    /// Requests preceding the matched one are considered to be lost, responses matching none of the requests are stale and ignored.
+    /// Whether the response completed an own request is told by the number of requests still outstanding.
+    ///
+    /// Plain responses without a name (e.g. `RPRT -4`) cannot be correlated and are matched to the oldest request,
+    /// as `rigctld` answers in order. If that request's response arrives late after the request was already considered lost,
+    /// the plain response is attributed to the next request instead. Named responses resynchronise the requests again.
+    pub(crate) fn complete(&mut self, response: &str) {
+        let position = match echoed(response) {
+            Some((name, args)) => {
+                let position = self
+                    .position(|r| r.name == name && r.args == args)
+                    .or_else(|| self.position(|r| r.name == name));
+                match position {
+                    Some(position) => position,
+                    None => return,
+                }
+            }
+            None => 0,
+        };
+
+        if position < self.outstanding.len() {
+            self.outstanding.drain(..=position);
+        }
+    }
+
+    /// Get the number of outstanding requests.
+    pub(crate) fn outstanding(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// Forget all outstanding requests, e.g. after the connection was closed.
+    pub(crate) fn clear(&mut self) {
+        self.outstanding.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(command_name(r";\get_freq VFOA"), "get_freq");
+        assert_eq!(command_name(r"\chk_vfo"), "chk_vfo");
+        assert_eq!(command_args(r";\get_level VFOA  RFPOWER"), "VFOA RFPOWER");
+        assert!(is_query(r";\get_freq VFOA"));
+        assert!(is_query("dump_caps"));
+        assert!(is_query(r"\chk_vfo"));
+        assert!(!is_query(r";\set_freq 7074000"));
+        assert_eq!(
+            echoed("get_freq:;Frequency: 7074000;RPRT 0"),
+            Some(("get_freq", String::new()))
+        );
+        assert_eq!(
+            echoed("set_freq: 7074000;RPRT 0"),
+            Some(("set_freq", String::from("7074000")))
+        );
+        assert_eq!(
+            echoed("get_level: VFOA RFPOWER\nLevel Value: 0.5\nRPRT 0"),
+            Some(("get_level", String::from("VFOA RFPOWER")))
+        );
+        assert_eq!(echoed("RPRT -1"), None);
+        assert_eq!(echoed("CHKVFO 1"), None);
+    }
+
+    #[test]
+    fn stale_responses() {
+        let mut requests = Requests::default();
+
+        // Response to a cancelled request of another command precedes the own one
+        requests.push(&[r";\get_mode"]);
+        requests.push(&[r";\get_freq"]);
+        requests.complete("get_mode:;Mode: USB;Passband: 2400;RPRT 0");
+        assert_eq!(requests.outstanding(), 1);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+
+        // Response arriving after its request was considered lost
+        requests.push(&[r";\get_freq"]);
+        requests.complete("get_mode:;Mode: USB;Passband: 2400;RPRT 0");
+        assert_eq!(requests.outstanding(), 1);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+
+        // Plain responses are matched in order
+        requests.push(&[r";\get_mode", r";\set_freq 7074000"]);
+        requests.complete("RPRT -11");
+        assert_eq!(requests.outstanding(), 1);
+        requests.complete("set_freq: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+
+        // A late plain response of a request considered lost is attributed to the next request
+        requests.push(&[r";\set_mode USB 2400"]);
+        requests.push(&[r";\get_freq"]);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        requests.push(&[r";\set_freq 7074000"]);
+        requests.complete("RPRT -4");
+        assert_eq!(requests.outstanding(), 0);
+    }
+
+    #[test]
+    fn lost_response() {
+        let mut requests = Requests::default();
+
+        requests.push(&[r";\get_ts", r";\get_freq"]);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+
+        // Lost response of the same command
+        requests.push(&[r";\get_freq"]);
+        requests.push(&[r";\get_freq"]);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+
+        // Same command several times within a write
+        requests.push(&[r";\get_freq", r";\get_freq"]);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 1);
+        requests.complete("get_freq:;Frequency: 7074000;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+
+        // Same command with other arguments
+        requests.push(&[r";\get_level STRENGTH", r";\get_level RFPOWER"]);
+        requests.complete("get_level: STRENGTH;Level Value: -12;RPRT 0");
+        assert_eq!(requests.outstanding(), 1);
+        requests.complete("get_level: RFPOWER;Level Value: 0.5;RPRT 0");
+        assert_eq!(requests.outstanding(), 0);
+    }
+}
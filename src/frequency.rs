@@ -0,0 +1,255 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rig::RigError;
+
+/// A radio frequency with a resolution of 1 Hz.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frequency(u64);
+
+/// Frequency implementation.
+impl Frequency {
+    /// Create a frequency from a value in Hz.
+    pub const fn from_hz(hz: u64) -> Frequency {
+        Frequency(hz)
+    }
+
+    /// Create a frequency from a value in kHz.
+    /// Panics if the frequency in Hz exceeds `u64::MAX`.
+    pub const fn from_khz(khz: u64) -> Frequency {
+        match khz.checked_mul(1_000) {
+            Some(hz) => Frequency(hz),
+            None => panic!("Frequency out of range"),
+        }
+    }
+
+    /// Create a frequency from a value in MHz.
+    /// Panics if the frequency in Hz exceeds `u64::MAX`.
+    pub const fn from_mhz(mhz: u64) -> Frequency {
+        match mhz.checked_mul(1_000_000) {
+            Some(hz) => Frequency(hz),
+            None => panic!("Frequency out of range"),
+        }
+    }
+
+    /// Get the frequency in Hz.
+    pub const fn hz(&self) -> u64 {
+        self.0
+    }
+
+    /// Shift the frequency by an offset (Hz).
+    /// Returns `None` if the resulting frequency would be negative or overflow.
+    pub fn checked_offset(self, offset: i64) -> Option<Frequency> {
+        self.0.checked_add_signed(offset).map(Frequency)
+    }
+
+    /// Shift the frequency by an offset (Hz).
+    /// The resulting frequency is clamped to the valid range.
+    pub fn saturating_offset(self, offset: i64) -> Frequency {
+        Frequency(self.0.saturating_add_signed(offset))
+    }
+
+    /// Round the frequency to the nearest multiple of a tuning step (Hz).
+    /// A step of 0 leaves the frequency unchanged.
+    pub fn round_to_step(self, step: u64) -> Frequency {
+        if step == 0 {
+            return self;
+        }
+
+        let below = self.0 - self.0 % step;
+        if self.0 - below >= step - (self.0 - below) {
+            Frequency(below.saturating_add(step))
+        } else {
+            Frequency(below)
+        }
+    }
+
+    /// Get the offset (Hz) from `other` to this frequency.
+    /// Returns `None` if the offset does not fit into an `i64`.
+    pub fn offset_from(self, other: Frequency) -> Option<i64> {
+        i64::try_from(i128::from(self.0) - i128::from(other.0)).ok()
+    }
+}
+
+impl From<u64> for Frequency {
+    fn from(hz: u64) -> Self {
+        Frequency(hz)
+    }
+}
+
+impl From<Frequency> for u64 {
+    fn from(freq: Frequency) -> Self {
+        freq.0
+    }
+}
+
+impl fmt::Display for Frequency {
+    /// Format the frequency in the unit an operator would use, e.g. `14.074 MHz` or `475.5 kHz`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (divisor, digits, unit, min_decimals) = match self.0 {
+            1_000_000_000.. => (1_000_000_000, 9, "GHz", 3),
+            1_000_000.. => (1_000_000, 6, "MHz", 3),
+            1_000.. => (1_000, 3, "kHz", 0),
+            _ => return write!(f, "{} Hz", self.0),
+        };
+
+        let fraction = format!("{:0width$}", self.0 % divisor, width = digits);
+        let fraction = fraction.trim_end_matches('0');
+        let fraction = format!("{:0<width$}", fraction, width = min_decimals);
+
+        if fraction.is_empty() {
+            write!(f, "{} {}", self.0 / divisor, unit)
+        } else {
+            write!(f, "{}.{} {}", self.0 / divisor, fraction, unit)
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = RigError;
+
+    /// Parse a frequency.
+    ///
+    /// Accepted are plain values in Hz (`14074000`), decimal values in Hz as printed by some hamlib versions
+    /// (`14074000.000000`), values with an unit (`14.074 MHz`, `7074k`, `1.2G`) and values with dots as
+    /// thousands separators (`144.300.000`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "hz" => 1,
+            "k" | "khz" => 1_000,
+            "m" | "mhz" => 1_000_000,
+            "g" | "ghz" => 1_000_000_000,
+            _ => return Err(RigError::ParseError),
+        };
+
+        let (integer, fraction) = if number.matches('.').count() > 1 {
+            // Dots as thousands separators
+            let mut groups = number.split('.');
+            let first = groups.next().unwrap_or_default();
+            if first.is_empty() || first.len() > 3 || groups.any(|g| g.len() != 3) {
+                return Err(RigError::ParseError);
+            }
+            (number.replace('.', ""), String::new())
+        } else {
+            let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+            (integer.to_string(), fraction.to_string())
+        };
+
+        if integer.is_empty() {
+            return Err(RigError::ParseError);
+        }
+
+        let integer = integer
+            .parse::<u64>()
+            .map_err(|_| RigError::ParseError)?
+            .checked_mul(multiplier)
+            .ok_or(RigError::ParseError)?;
+
+        // Scale the fraction to Hz and round to the nearest Hz
+        let mut scaled: u64 = 0;
+        let mut scale = multiplier;
+        let mut round_up = false;
+        for digit in fraction.bytes().map(|b| u64::from(b - b'0')) {
+            if scale == 1 {
+                round_up = digit >= 5;
+                break;
+            }
+            scale /= 10;
+            scaled += digit * scale;
+        }
+        let hz = integer
+            .checked_add(scaled + u64::from(round_up))
+            .ok_or(RigError::ParseError)?;
+
+        Ok(Frequency(hz))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("14074000".parse(), Ok(Frequency::from_hz(14_074_000)));
+        assert_eq!(
+            "14074000.000000".parse(),
+            Ok(Frequency::from_hz(14_074_000))
+        );
+        assert_eq!("14.074 MHz".parse(), Ok(Frequency::from_hz(14_074_000)));
+        assert_eq!("7074k".parse(), Ok(Frequency::from_khz(7_074)));
+        assert_eq!("475.5 kHz".parse(), Ok(Frequency::from_hz(475_500)));
+        assert_eq!("1.2G".parse(), Ok(Frequency::from_mhz(1_200)));
+        assert_eq!("144.300.000".parse(), Ok(Frequency::from_khz(144_300)));
+        assert_eq!("7074000.6".parse(), Ok(Frequency::from_hz(7_074_001)));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("".parse::<Frequency>().is_err());
+        assert!("MHz".parse::<Frequency>().is_err());
+        assert!("14.074 MBit".parse::<Frequency>().is_err());
+        assert!("144.30.000".parse::<Frequency>().is_err());
+        assert!("-7074k".parse::<Frequency>().is_err());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(Frequency::from_hz(14_074_000).to_string(), "14.074 MHz");
+        assert_eq!(Frequency::from_mhz(144).to_string(), "144.000 MHz");
+        assert_eq!(Frequency::from_hz(14_074_123).to_string(), "14.074123 MHz");
+        assert_eq!(Frequency::from_hz(475_500).to_string(), "475.5 kHz");
+        assert_eq!(Frequency::from_khz(136).to_string(), "136 kHz");
+        assert_eq!(
+            Frequency::from_hz(10_489_550_000).to_string(),
+            "10.48955 GHz"
+        );
+        assert_eq!(Frequency::from_hz(50).to_string(), "50 Hz");
+    }
+
+    #[test]
+    fn format_roundtrip() {
+        let freq = Frequency::from_hz(7_074_500);
+        assert_eq!(freq.to_string().parse(), Ok(freq));
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        Frequency::from_mhz(u64::MAX / 1_000);
+    }
+
+    #[test]
+    fn offset() {
+        let freq = Frequency::from_khz(7_074);
+        assert_eq!(
+            freq.checked_offset(-1_000),
+            Some(Frequency::from_khz(7_073))
+        );
+        assert_eq!(freq.checked_offset(-8_000_000), None);
+        assert_eq!(freq.saturating_offset(-8_000_000), Frequency::from_hz(0));
+        assert_eq!(Frequency::from_khz(7_073).offset_from(freq), Some(-1_000));
+    }
+
+    #[test]
+    fn round_to_step() {
+        let freq = Frequency::from_hz(7_074_049);
+        assert_eq!(freq.round_to_step(100), Frequency::from_hz(7_074_000));
+        assert_eq!(
+            Frequency::from_hz(7_074_050).round_to_step(100),
+            Frequency::from_hz(7_074_100)
+        );
+        assert_eq!(freq.round_to_step(50_000), Frequency::from_khz(7_050));
+        assert_eq!(freq.round_to_step(0), freq);
+    }
+}
@@ -0,0 +1,397 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::future::poll_fn;
+use std::task::Poll;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time;
+
+use crate::cat::CatTerminator;
+use crate::clock::Clock;
+use crate::conf::ConfToken;
+use crate::framing::is_query;
+use crate::frequency::Frequency;
+use crate::rig::{Mode, Passband, Rig, RigError, ScanFunction, Vfo};
+use crate::transport::BoxFuture;
+
+/// Command queued to the connection task.
+type Job = Box<dyn for<'a> FnOnce(&'a mut Rig) -> BoxFuture<'a, ()> + Send>;
+
+/// Number of commands that may be queued before callers have to wait.
+const QUEUE_SIZE: usize = 32;
+
+/// Default time a command may wait within the queue before its execution starts.
+const QUEUE_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+/// Priority of a command queued to the connection task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Commands changing the rig, e.g. PTT or `set_frequency`, overtake queued commands of normal priority
+    High,
+    /// Queries, e.g. polling the frequency or meters
+    Normal,
+}
+
+/// Priority implementation.
+impl Priority {
+    /// Get the priority of a command or of a method of `RigHandle` named after it, queries have normal priority.
+    fn of(command: &str) -> Priority {
+        if is_query(command) {
+            Priority::Normal
+        } else {
+            Priority::High
+        }
+    }
+}
+
+/// Clonable handle to a connection to `rigctld`.
+///
+/// The connection is owned by a background task which executes the commands of all handles one after another.
+/// Queued commands of high priority are executed first, see `Priority`.
+/// While idle, the task sends keepalives if enabled by `Rig::set_keepalive`.
+/// A lost connection is then re-established one attempt per keepalive interval, queued commands are executed in between.
+/// The task ends as soon as the last handle is dropped.
+///
+/// By default, a command may wait within the queue for 1 s (see `set_queue_timeout`).
+/// Its execution is limited by the timeouts of the `Rig` (see `Rig::set_timeouts`), e.g. 10 s for slow commands like `\dump_conf`.
+#[derive(Clone)]
+pub struct RigHandle {
+    urgent: mpsc::Sender<Job>,
+    jobs: mpsc::Sender<Job>,
+    queue_timeout: time::Duration,
+    timeout: Option<time::Duration>,
+}
+
+/// Generate a method forwarding a command to the connection task.
+macro_rules! forward {
+    ($(#[$doc:meta])* $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        $(#[$doc])*
+        pub async fn $name(&self, $($arg: $ty),*) -> Result<$ret, RigError> {
+            let priority = Priority::of(stringify!($name));
+            self.call_with_priority(priority, move |rig| Box::pin(rig.$name($($arg),*))).await
+        }
+    };
+}
+
+/// RigHandle implementation.
+impl RigHandle {
+    /// Move a connected `Rig` into a background task and get a handle to it.
+    /// Must be called within a tokio runtime.
+    pub fn spawn(mut rig: Rig) -> RigHandle {
+        let (urgent, mut urgent_queue) = mpsc::channel::<Job>(QUEUE_SIZE);
+        let (jobs, mut queue) = mpsc::channel::<Job>(QUEUE_SIZE);
+
+        tokio::spawn(async move {
+            loop {
+                let next = RigHandle::next_job(&mut urgent_queue, &mut queue);
+                let job = match rig.keepalive_due() {
+                    Some(due) => match time::timeout_at(due, next).await {
+                        Ok(job) => job,
+                        Err(_) => {
+                            let _ = rig.keepalive().await;
+                            continue;
+                        }
+                    },
+                    None => next.await,
+                };
+
+                match job {
+                    Some(job) => job(&mut rig).await,
+                    None => break,
+                }
+            }
+            rig.disconnect();
+        });
+
+        RigHandle {
+            urgent,
+            jobs,
+            queue_timeout: QUEUE_TIMEOUT,
+            timeout: None,
+        }
+    }
+
+    /// Receive the next job, jobs of high priority first.
+    /// Returns `None` as soon as all handles are dropped.
+    async fn next_job(
+        urgent: &mut mpsc::Receiver<Job>,
+        jobs: &mut mpsc::Receiver<Job>,
+    ) -> Option<Job> {
+        poll_fn(|cx| match urgent.poll_recv(cx) {
+            Poll::Ready(Some(job)) => Poll::Ready(Some(job)),
+            // Both queues are closed at once, since every handle holds both senders
+            Poll::Ready(None) | Poll::Pending => jobs.poll_recv(cx),
+        })
+        .await
+    }
+
+    /// Set the time a command issued through this handle may wait within the queue before its execution starts.
+    /// Commands which time out within the queue are dropped without being sent to `rigctld`.
+    /// Clones of the handle inherit the timeout.
+    pub fn set_queue_timeout(&mut self, timeout: time::Duration) {
+        self.queue_timeout = timeout;
+    }
+
+    /// Set a fixed timeout of commands issued through this handle, overriding the queue timeout and the timeouts of the `Rig`.
+    /// The timeout covers the time a command waits within the queue as well as its execution, including the delay of a `Throttle`.
+    /// Commands which time out before their execution starts are dropped without being sent to `rigctld`.
+    /// Clones of the handle inherit the timeout.
+    pub fn set_timeout(&mut self, timeout: time::Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Check if the connection task is still running.
+    pub fn is_alive(&self) -> bool {
+        !self.jobs.is_closed()
+    }
+
+    /// Execute an arbitrary command on the `Rig` owned by the connection task with the given priority.
+    ///
+    /// # Arguments:
+    ///
+    /// * `priority`: Priority of the command, commands of high priority overtake queued commands of normal priority
+    /// * `command`: Closure returning the boxed future of the command, e.g. `|rig| Box::pin(rig.get_frequency())`
+    ///
+    /// # Result
+    ///
+    /// Returns the result of the command or in case of an error the error cause.
+    pub async fn call_with_priority<T, F>(
+        &self,
+        priority: Priority,
+        command: F,
+    ) -> Result<T, RigError>
+    where
+        T: Send + 'static,
+        F: for<'a> FnOnce(&'a mut Rig) -> BoxFuture<'a, Result<T, RigError>> + Send + 'static,
+    {
+        self.submit(priority, self.timeout, command).await
+    }
+
+    /// Execute an arbitrary command on the `Rig` owned by the connection task with normal priority.
+    ///
+    /// # Arguments:
+    ///
+    /// * `command`: Closure returning the boxed future of the command, e.g. `|rig| Box::pin(rig.get_frequency())`
+    ///
+    /// # Result
+    ///
+    /// Returns the result of the command or in case of an error the error cause.
+    pub async fn call<T, F>(&self, command: F) -> Result<T, RigError>
+    where
+        T: Send + 'static,
+        F: for<'a> FnOnce(&'a mut Rig) -> BoxFuture<'a, Result<T, RigError>> + Send + 'static,
+    {
+        self.submit(Priority::Normal, self.timeout, command).await
+    }
+
+    /// Execute an arbitrary command on the `Rig` owned by the connection task with an individual timeout and normal priority.
+    /// On timeout the command is still executed if its execution already started, but its result is discarded.
+    ///
+    /// # Arguments:
+    ///
+    /// * `timeout`: Timeout of the command
+    /// * `command`: Closure returning the boxed future of the command
+    ///
+    /// # Result
+    ///
+    /// Returns the result of the command or in case of an error the error cause.
+    pub async fn call_with_timeout<T, F>(
+        &self,
+        timeout: time::Duration,
+        command: F,
+    ) -> Result<T, RigError>
+    where
+        T: Send + 'static,
+        F: for<'a> FnOnce(&'a mut Rig) -> BoxFuture<'a, Result<T, RigError>> + Send + 'static,
+    {
+        self.submit(Priority::Normal, Some(timeout), command).await
+    }
+
+    /// Queue a command to the connection task and wait for its result.
+    /// Without a fixed timeout, the wait within the queue is limited by the queue timeout and the execution by the timeouts of the `Rig`.
+    async fn submit<T, F>(
+        &self,
+        priority: Priority,
+        timeout: Option<time::Duration>,
+        command: F,
+    ) -> Result<T, RigError>
+    where
+        T: Send + 'static,
+        F: for<'a> FnOnce(&'a mut Rig) -> BoxFuture<'a, Result<T, RigError>> + Send + 'static,
+    {
+        let queue = match priority {
+            Priority::High => &self.urgent,
+            Priority::Normal => &self.jobs,
+        };
+        let (reply, result) = oneshot::channel();
+        let (start, started) = oneshot::channel();
+        let job: Job = Box::new(move |rig| {
+            Box::pin(async move {
+                // Skip commands whose caller gave up while they were queued, e.g. behind throttled commands
+                if start.send(()).is_err() {
+                    return;
+                }
+                let _ = reply.send(command(rig).await);
+            })
+        });
+
+        let queued = async {
+            queue
+                .send(job)
+                .await
+                .map_err(|_| RigError::ConnectionLost)?;
+            started.await.map_err(|_| RigError::ConnectionLost)
+        };
+        let executed = async { result.await.map_err(|_| RigError::ConnectionLost)? };
+
+        match timeout {
+            Some(timeout) => time::timeout(timeout, async {
+                queued.await?;
+                executed.await
+            })
+            .await
+            .map_err(|_| RigError::CommunicationTimeout)?,
+            None => {
+                time::timeout(self.queue_timeout, queued)
+                    .await
+                    .map_err(|_| RigError::CommunicationTimeout)??;
+                executed.await
+            }
+        }
+    }
+
+    forward!(
+        /// Get the rigs frequency, see `Rig::get_frequency`.
+        get_frequency() -> Frequency
+    );
+    forward!(
+        /// Set the rigs frequency, see `Rig::set_frequency`.
+        set_frequency(frequency: Frequency) -> ()
+    );
+    forward!(
+        /// Get the rigs tuning step, see `Rig::get_ts`.
+        get_ts() -> u64
+    );
+    forward!(
+        /// Set the rigs tuning step, see `Rig::set_ts`.
+        set_ts(step: u64) -> ()
+    );
+    forward!(
+        /// Tune the rig by a number of tuning steps, see `Rig::step_frequency`.
+        step_frequency(steps: i64) -> Frequency
+    );
+    forward!(
+        /// Move the rigs frequency onto the grid of the tuning step, see `Rig::snap_frequency`.
+        snap_frequency() -> Frequency
+    );
+    forward!(
+        /// Get the rigs mode, see `Rig::get_mode`.
+        get_mode() -> (Mode, Passband)
+    );
+    forward!(
+        /// Set the rigs mode, see `Rig::set_mode`.
+        set_mode(mode: Mode, passband: Passband) -> ()
+    );
+    forward!(
+        /// Get the currently selected VFO, see `Rig::get_vfo`.
+        get_vfo() -> Vfo
+    );
+    forward!(
+        /// Check if the rig is transmitting, see `Rig::get_ptt`.
+        get_ptt() -> bool
+    );
+    forward!(
+        /// Switch the rig between transmitting and receiving, see `Rig::set_ptt`.
+        set_ptt(ptt: bool) -> ()
+    );
+    forward!(
+        /// Get the split state of the rig, see `Rig::get_split_vfo`.
+        get_split_vfo() -> (bool, Vfo)
+    );
+    forward!(
+        /// Control the rigs internal scan, see `Rig::scan`.
+        scan(function: ScanFunction, channel: u32) -> ()
+    );
+    forward!(
+        /// Get the rigs clock, see `Rig::get_clock`.
+        get_clock() -> Clock
+    );
+    forward!(
+        /// Set the rigs clock to the time of the host system, see `Rig::sync_clock`.
+        sync_clock(utc_offset: i16) -> Clock
+    );
+    forward!(
+        /// Get the rigs RF power level, see `Rig::get_rf_power`.
+        get_rf_power() -> f32
+    );
+    forward!(
+        /// Set the rigs RF power level, see `Rig::set_rf_power`.
+        set_rf_power(power: f32) -> ()
+    );
+    forward!(
+        /// Get the rigs RF power in watts, see `Rig::get_rf_power_watts`.
+        get_rf_power_watts() -> f32
+    );
+    forward!(
+        /// Set the rigs RF power in watts, see `Rig::set_rf_power_watts`.
+        set_rf_power_watts(watts: f32) -> ()
+    );
+    forward!(
+        /// Get the signal strength (S-meter) of the rig, see `Rig::get_strength`.
+        get_strength() -> i32
+    );
+    forward!(
+        /// Check if the front panel of the rig is locked, see `Rig::get_lock_mode`.
+        get_lock_mode() -> bool
+    );
+    forward!(
+        /// Lock or unlock the front panel of the rig, see `Rig::set_lock_mode`.
+        set_lock_mode(locked: bool) -> ()
+    );
+    forward!(
+        /// Get the configuration tokens of the rig backend, see `Rig::dump_conf`.
+        dump_conf() -> Vec<ConfToken>
+    );
+
+    /// Set the rigs clock, see `Rig::set_clock`.
+    pub async fn set_clock(&self, clock: Clock) -> Result<(), RigError> {
+        self.call_with_priority(Priority::High, move |rig| {
+            Box::pin(async move { rig.set_clock(&clock).await })
+        })
+        .await
+    }
+
+    /// Get the value of a configuration token of the rig backend, see `Rig::get_conf`.
+    pub async fn get_conf(&self, token: String) -> Result<String, RigError> {
+        self.call(move |rig| Box::pin(async move { rig.get_conf(&token).await }))
+            .await
+    }
+
+    /// Set the value of a configuration token of the rig backend, see `Rig::set_conf`.
+    pub async fn set_conf(&self, token: String, value: String) -> Result<(), RigError> {
+        self.call_with_priority(Priority::High, move |rig| {
+            Box::pin(async move { rig.set_conf(&token, &value).await })
+        })
+        .await
+    }
+
+    /// Send raw CAT bytes to the rig and read its reply, see `Rig::send_raw_cat`.
+    pub async fn send_raw_cat(
+        &self,
+        bytes: Vec<u8>,
+        terminator: CatTerminator,
+    ) -> Result<Vec<u8>, RigError> {
+        self.call_with_priority(Priority::High, move |rig| {
+            Box::pin(async move { rig.send_raw_cat(&bytes, terminator).await })
+        })
+        .await
+    }
+}
+
+impl From<Rig> for RigHandle {
+    fn from(rig: Rig) -> Self {
+        RigHandle::spawn(rig)
+    }
+}
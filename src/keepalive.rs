@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use tokio::time;
+
+/// Health of the link to `rigctld` as seen by the latest commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkHealth {
+    /// `rigctld` answers in time
+    Healthy,
+    /// `rigctld` answers slowly or some commands timed out
+    Degraded,
+    /// `rigctld` does not answer anymore or the connection is closed
+    Lost,
+}
+
+/// Settings of the keepalive and the link health assessment.
+///
+/// While idle, the keepalive sends `\chk_vfo` at the given interval.
+/// The command is answered by `rigctld` itself and thus does not cause any traffic to the rig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keepalive {
+    interval: time::Duration,
+    degraded_latency: time::Duration,
+    max_failures: u32,
+}
+
+impl Default for Keepalive {
+    /// Get default keepalive settings.
+    /// Sends a keepalive every 5 s, considers a round-trip time above 500 ms as degraded and the link as lost after 3 consecutive failures.
+    fn default() -> Self {
+        Keepalive {
+            interval: time::Duration::from_secs(5),
+            degraded_latency: time::Duration::from_millis(500),
+            max_failures: 3,
+        }
+    }
+}
+
+/// Keepalive implementation.
+impl Keepalive {
+    /// Set the interval between two keepalives while no other commands are sent.
+    pub fn set_interval(mut self, interval: time::Duration) -> Keepalive {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the round-trip time above which the link is considered degraded.
+    pub fn set_degraded_latency(mut self, latency: time::Duration) -> Keepalive {
+        self.degraded_latency = latency;
+        self
+    }
+
+    /// Set the number of consecutive failed commands after which the link is considered lost.
+    pub fn set_max_failures(mut self, failures: u32) -> Keepalive {
+        self.max_failures = failures.max(1);
+        self
+    }
+
+    /// Get the interval between two keepalives.
+    pub fn get_interval(&self) -> time::Duration {
+        self.interval
+    }
+
+    /// Assess the link health from the latest round-trip time and the number of consecutive failures.
+    pub(crate) fn assess(&self, latency: Option<time::Duration>, failures: u32) -> LinkHealth {
+        if failures >= self.max_failures {
+            LinkHealth::Lost
+        } else if failures > 0 || latency.is_some_and(|l| l > self.degraded_latency) {
+            LinkHealth::Degraded
+        } else {
+            LinkHealth::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assess() {
+        let keepalive = Keepalive::default()
+            .set_degraded_latency(time::Duration::from_millis(100))
+            .set_max_failures(2);
+        let fast = Some(time::Duration::from_millis(20));
+        let slow = Some(time::Duration::from_millis(200));
+
+        assert_eq!(keepalive.assess(fast, 0), LinkHealth::Healthy);
+        assert_eq!(keepalive.assess(None, 0), LinkHealth::Healthy);
+        assert_eq!(keepalive.assess(slow, 0), LinkHealth::Degraded);
+        assert_eq!(keepalive.assess(fast, 1), LinkHealth::Degraded);
+        assert_eq!(keepalive.assess(fast, 2), LinkHealth::Lost);
+    }
+}
@@ -2,8 +2,41 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cache;
+pub mod cat;
+pub mod clock;
+pub mod conf;
 pub mod daemon;
+mod framing;
+pub mod frequency;
+pub mod handle;
+pub mod keepalive;
+#[cfg(feature = "multicast")]
+pub mod multicast;
+pub mod reconnect;
 pub mod rig;
+pub mod throttle;
+pub mod timeouts;
+mod trace;
+pub mod transport;
+pub mod watcher;
 
+pub use batch::*;
+pub use cat::*;
+pub use clock::*;
+pub use conf::*;
 pub use daemon::*;
+pub use frequency::*;
+pub use handle::*;
+pub use keepalive::*;
+#[cfg(feature = "multicast")]
+pub use multicast::*;
+pub use reconnect::*;
 pub use rig::*;
+pub use throttle::*;
+pub use timeouts::*;
+pub use transport::*;
+pub use watcher::*;
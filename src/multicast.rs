@@ -0,0 +1,376 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::str::FromStr;
+
+use serde::Deserialize;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+
+use crate::frequency::Frequency;
+use crate::rig::{Mode, RigError, Vfo};
+
+/// Maximum size of a packet published by `rigctld`.
+const PACKET_SIZE: usize = 65_536;
+
+/// State of the rig as published by `rigctld` via multicast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RigState {
+    /// Sequence number of the packet
+    pub seq: u64,
+    /// Name of the rig model
+    pub name: String,
+    /// Error message of the rig backend, if any
+    pub error: Option<String>,
+    /// Whether split is enabled
+    pub split: bool,
+    /// Transmit VFO while split is enabled
+    pub split_vfo: Option<Vfo>,
+    /// Whether satellite mode is enabled
+    pub sat_mode: bool,
+    /// State of the VFOs
+    pub vfos: Vec<VfoState>,
+    /// Spectrum scope data, only present if the rig provides it
+    pub spectra: Vec<Spectrum>,
+}
+
+/// State of a single VFO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VfoState {
+    pub vfo: Vfo,
+    pub frequency: Frequency,
+    /// Mode of the VFO, `None` if unknown to this crate
+    pub mode: Option<Mode>,
+    /// Passband width (Hz)
+    pub width: u32,
+    pub ptt: bool,
+    /// Whether the VFO is used for receiving
+    pub rx: bool,
+    /// Whether the VFO is used for transmitting
+    pub tx: bool,
+}
+
+/// Span mode of a spectrum scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumMode {
+    /// Span around the center frequency
+    Center,
+    /// Fixed span between the lower and upper edge
+    Fixed,
+}
+
+/// Spectrum scope data of the rig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spectrum {
+    pub id: u32,
+    pub name: String,
+    pub mode: SpectrumMode,
+    pub min_level: i32,
+    pub max_level: i32,
+    /// Strength of `min_level` (dB)
+    pub min_strength: i32,
+    /// Strength of `max_level` (dB)
+    pub max_strength: i32,
+    pub center_frequency: Frequency,
+    /// Width of the span (Hz)
+    pub span: u64,
+    pub low_frequency: Frequency,
+    pub high_frequency: Frequency,
+    /// One level per bin from the low to the high frequency
+    pub data: Vec<u8>,
+}
+
+/// Packet as serialized by `rigctld`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Packet {
+    #[serde(default)]
+    seq: u64,
+    rig: PacketRig,
+    #[serde(default)]
+    vfos: Vec<PacketVfo>,
+    #[serde(default)]
+    spectra: Vec<PacketSpectrum>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PacketRig {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    error_msg: String,
+    #[serde(default)]
+    split: bool,
+    #[serde(default)]
+    split_vfo: String,
+    #[serde(default)]
+    sat_mode: bool,
+}
+
+#[derive(Deserialize)]
+struct PacketVfo {
+    name: String,
+    freq: f64,
+    #[serde(default)]
+    mode: String,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    ptt: bool,
+    #[serde(default)]
+    rx: bool,
+    #[serde(default)]
+    tx: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PacketSpectrum {
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    mode: String,
+    min_level: i32,
+    max_level: i32,
+    min_strength: i32,
+    max_strength: i32,
+    center_freq: f64,
+    span: f64,
+    low_freq: f64,
+    high_freq: f64,
+    data: String,
+}
+
+/// Decode a packet published by `rigctld`.
+pub(crate) fn decode_packet(packet: &[u8]) -> Result<RigState, RigError> {
+    let packet: Packet = serde_json::from_slice(packet).map_err(|_| RigError::ParseError)?;
+
+    let vfos = packet
+        .vfos
+        .into_iter()
+        .map(|v| {
+            Ok(VfoState {
+                vfo: Vfo::from_str(&v.name)?,
+                frequency: Frequency::from_hz(v.freq.round() as u64),
+                mode: Mode::from_str(&v.mode).ok(),
+                width: v.width,
+                ptt: v.ptt,
+                rx: v.rx,
+                tx: v.tx,
+            })
+        })
+        .collect::<Result<Vec<_>, RigError>>()?;
+
+    let spectra = packet
+        .spectra
+        .into_iter()
+        .map(|s| {
+            Ok(Spectrum {
+                id: s.id,
+                name: s.name,
+                mode: match s.mode.as_str() {
+                    "CENTER" => SpectrumMode::Center,
+                    "FIXED" => SpectrumMode::Fixed,
+                    _ => return Err(RigError::ParseError),
+                },
+                min_level: s.min_level,
+                max_level: s.max_level,
+                min_strength: s.min_strength,
+                max_strength: s.max_strength,
+                center_frequency: Frequency::from_hz(s.center_freq.round() as u64),
+                span: s.span.round() as u64,
+                low_frequency: Frequency::from_hz(s.low_freq.round() as u64),
+                high_frequency: Frequency::from_hz(s.high_freq.round() as u64),
+                data: decode_hex(&s.data)?,
+            })
+        })
+        .collect::<Result<Vec<_>, RigError>>()?;
+
+    Ok(RigState {
+        seq: packet.seq,
+        name: packet.rig.name,
+        error: Some(packet.rig.error_msg).filter(|e| !e.is_empty()),
+        split: packet.rig.split,
+        split_vfo: Vfo::from_str(&packet.rig.split_vfo).ok(),
+        sat_mode: packet.rig.sat_mode,
+        vfos,
+        spectra,
+    })
+}
+
+/// Decode the hex encoded spectrum data.
+fn decode_hex(data: &str) -> Result<Vec<u8>, RigError> {
+    if data.len() % 2 == 1 {
+        return Err(RigError::ParseError);
+    }
+
+    (0..data.len())
+        .step_by(2)
+        .map(|i| {
+            data.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or(RigError::ParseError)
+        })
+        .collect()
+}
+
+/// Listener for the rig state `rigctld` publishes via multicast (`Daemon::set_multicast_addr`).
+/// Requires hamlib 4.6 or newer.
+///
+/// Several listeners on the same host may share the port, which allows multiple clients to follow the rig without polling it.
+pub struct MulticastListener {
+    socket: UdpSocket,
+    port: u16,
+    command_addr: Option<SocketAddr>,
+    buffer: Vec<u8>,
+}
+
+/// MulticastListener implementation.
+impl MulticastListener {
+    /// Listen for packets published on the given address and port, e.g. `224.0.0.1:4532`.
+    /// The port 0 picks any free port, see `get_port`.
+    /// Multicast groups are joined on the default interface, other addresses receive unicast packets only.
+    /// Must be called within a tokio runtime.
+    pub fn bind(addr: Ipv4Addr, port: u16) -> Result<MulticastListener, RigError> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+            .map_err(|_| RigError::ConnectionError)?;
+        socket
+            .set_reuse_address(true)
+            .and_then(|_| socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port).into()))
+            .and_then(|_| socket.set_nonblocking(true))
+            .map_err(|_| RigError::ConnectionError)?;
+
+        if addr.is_multicast() {
+            socket
+                .join_multicast_v4(&addr, &Ipv4Addr::UNSPECIFIED)
+                .map_err(|_| RigError::ConnectionError)?;
+        }
+
+        let socket = UdpSocket::from_std(socket.into()).map_err(|_| RigError::ConnectionError)?;
+        let port = socket
+            .local_addr()
+            .map_err(|_| RigError::ConnectionError)?
+            .port();
+
+        Ok(MulticastListener {
+            socket,
+            port,
+            command_addr: None,
+            buffer: vec![0; PACKET_SIZE],
+        })
+    }
+
+    /// Get the port the listener is bound to.
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+
+    /// Set the address `rigctld` receives multicast commands on (`Daemon::set_multicast_cmd_addr`).
+    pub fn set_command_addr(&mut self, addr: SocketAddr) {
+        self.command_addr = Some(addr);
+    }
+
+    /// Wait for the next packet and decode it.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the state of the rig or in case of an error the error cause.
+    /// Invalid packets result in `RigError::ParseError`, the listener may be used further on.
+    pub async fn recv(&mut self) -> Result<RigState, RigError> {
+        let len = self
+            .socket
+            .recv(&mut self.buffer)
+            .await
+            .map_err(|_| RigError::ConnectionError)?;
+
+        decode_packet(&self.buffer[..len])
+    }
+
+    /// Send a command to `rigctld` via multicast, e.g. `\set_freq 14074000`.
+    /// Requires a command address, see `set_command_addr`.
+    ///
+    /// # Arguments:
+    ///
+    /// * `command`: Command in the format also used via TCP
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn send_command(&self, command: &str) -> Result<(), RigError> {
+        let addr = self.command_addr.ok_or(RigError::NotConnected)?;
+
+        self.socket
+            .send_to(format!("{}\n", command).as_bytes(), addr)
+            .await
+            .map(|_| ())
+            .map_err(|_| RigError::ConnectionError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET: &str = r#"{
+        "app": "Hamlib", "version": "4.6", "seq": 42, "time": "2024-05-01T12:00:00.000000+0000", "crc": 0,
+        "rig": {
+            "id": {"model": "1", "endpoint": "", "process": "", "deviceId": ""},
+            "status": "OK", "errorMsg": "", "name": "Dummy",
+            "split": true, "splitVfo": "VFOB", "satMode": false,
+            "modes": ["AM", "CW", "USB", "LSB", "FM"]
+        },
+        "vfos": [
+            {"name": "VFOA", "freq": 14074000, "mode": "USB", "width": 2400, "ptt": false, "rx": true, "tx": false},
+            {"name": "VFOB", "freq": 14076000.0, "mode": "PKTUSB", "width": 3000, "ptt": true, "rx": false, "tx": true}
+        ],
+        "spectra": [
+            {"id": 0, "name": "Main", "type": "CENTER", "minLevel": 0, "maxLevel": 160,
+             "minStrength": -100, "maxStrength": 0, "centerFreq": 14074000, "span": 25000,
+             "lowFreq": 14061500, "highFreq": 14086500, "length": 4, "data": "00107FA0"}
+        ]
+    }"#;
+
+    #[test]
+    fn decode() {
+        let state = decode_packet(PACKET.as_bytes()).unwrap();
+
+        assert_eq!(state.seq, 42);
+        assert_eq!(state.name, "Dummy");
+        assert_eq!(state.error, None);
+        assert_eq!((state.split, state.split_vfo), (true, Some(Vfo::VFOB)));
+        assert_eq!(
+            state.vfos[0],
+            VfoState {
+                vfo: Vfo::VFOA,
+                frequency: Frequency::from_khz(14074),
+                mode: Some(Mode::USB),
+                width: 2400,
+                ptt: false,
+                rx: true,
+                tx: false,
+            }
+        );
+        assert_eq!(state.vfos[1].mode, Some(Mode::PKTUSB));
+        assert!(state.vfos[1].ptt);
+        assert_eq!(state.spectra[0].mode, SpectrumMode::Center);
+        assert_eq!(state.spectra[0].low_frequency, Frequency::from_hz(14061500));
+        assert_eq!(state.spectra[0].data, vec![0x00, 0x10, 0x7F, 0xA0]);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(decode_packet(b"{\"seq\": 1"), Err(RigError::ParseError));
+        assert_eq!(
+            decode_packet(PACKET.replace("00107FA0", "0010F").as_bytes()),
+            Err(RigError::ParseError)
+        );
+    }
+}
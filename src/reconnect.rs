@@ -0,0 +1,164 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use tokio::time;
+
+/// State of the connection to `rigctld`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    /// Connected to `rigctld`
+    Connected,
+    /// Connection lost, trying to re-establish it
+    Reconnecting {
+        /// Number of the current attempt, starting at 1
+        attempt: u32,
+    },
+    /// Not connected to `rigctld`
+    Disconnected,
+}
+
+/// Policy to re-establish a lost connection to `rigctld`.
+///
+/// The delay before each attempt grows exponentially from the initial delay up to the maximum delay.
+/// A random jitter is applied to the delay to keep several clients from reconnecting in lockstep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    initial_delay: time::Duration,
+    max_delay: time::Duration,
+    multiplier: f64,
+    max_attempts: Option<u32>,
+    jitter: f64,
+}
+
+impl Default for ReconnectPolicy {
+    /// Get default reconnect policy.
+    /// Starts with a delay of 250 ms, doubles it up to 10 s and gives up after 10 attempts.
+    /// The jitter is 10 % of the delay.
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: time::Duration::from_millis(250),
+            max_delay: time::Duration::from_secs(10),
+            multiplier: 2.0,
+            max_attempts: Some(10),
+            jitter: 0.1,
+        }
+    }
+}
+
+/// ReconnectPolicy implementation.
+impl ReconnectPolicy {
+    /// Set the delay before the first attempt.
+    pub fn set_initial_delay(mut self, delay: time::Duration) -> ReconnectPolicy {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the maximum delay between two attempts.
+    pub fn set_max_delay(mut self, delay: time::Duration) -> ReconnectPolicy {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the factor the delay grows by after each failed attempt.
+    pub fn set_multiplier(mut self, multiplier: f64) -> ReconnectPolicy {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Set the maximum number of attempts, `None` to try forever.
+    pub fn set_max_attempts(mut self, attempts: Option<u32>) -> ReconnectPolicy {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Set the jitter as fraction of the delay (0.0..1.0).
+    pub fn set_jitter(mut self, jitter: f64) -> ReconnectPolicy {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Check if the given attempt, starting at 1, is still allowed.
+    pub(crate) fn allows_attempt(&self, attempt: u32) -> bool {
+        match self.max_attempts {
+            Some(max) => attempt <= max,
+            None => true,
+        }
+    }
+
+    /// Get the delay before the given attempt, starting at 1.
+    pub(crate) fn delay(&self, attempt: u32) -> time::Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_delay.as_secs_f64());
+        let jitter = (random_unit() * 2.0 - 1.0) * self.jitter;
+
+        // A huge maximum delay, e.g. `Duration::MAX` for no limit, may exceed `Duration` with jitter
+        time::Duration::try_from_secs_f64((delay * (1.0 + jitter)).max(0.0))
+            .unwrap_or(self.max_delay)
+    }
+}
+
+/// Get a random number within 0.0..1.0.
+/// The randomly seeded hasher of the standard library is good enough to spread the reconnect attempts.
+fn random_unit() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_delay() {
+        let policy = ReconnectPolicy::default()
+            .set_initial_delay(time::Duration::MAX)
+            .set_max_delay(time::Duration::MAX)
+            .set_jitter(1.0);
+
+        for attempt in 1..4 {
+            policy.delay(attempt);
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = ReconnectPolicy::default()
+            .set_initial_delay(time::Duration::from_millis(100))
+            .set_max_delay(time::Duration::from_millis(1000))
+            .set_jitter(0.0);
+
+        assert_eq!(policy.delay(1), time::Duration::from_millis(100));
+        assert_eq!(policy.delay(2), time::Duration::from_millis(200));
+        assert_eq!(policy.delay(4), time::Duration::from_millis(800));
+        assert_eq!(policy.delay(5), time::Duration::from_millis(1000));
+        assert_eq!(policy.delay(100), time::Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn jitter() {
+        let policy = ReconnectPolicy::default()
+            .set_initial_delay(time::Duration::from_millis(100))
+            .set_jitter(0.5);
+
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= time::Duration::from_millis(50));
+            assert!(delay <= time::Duration::from_millis(150));
+        }
+    }
+
+    #[test]
+    fn max_attempts() {
+        let policy = ReconnectPolicy::default().set_max_attempts(Some(3));
+        assert!(policy.allows_attempt(3));
+        assert!(!policy.allows_attempt(4));
+        assert!(ReconnectPolicy::default()
+            .set_max_attempts(None)
+            .allows_attempt(u32::MAX));
+    }
+}
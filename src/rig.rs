@@ -5,14 +5,32 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::process::ExitStatus;
 use std::str::FromStr;
 use thiserror::Error;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
 use tokio::time;
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::batch::{Batch, Reply};
+use crate::cache::ReadCache;
+use crate::cat::{self, CatTerminator};
+use crate::clock::Clock;
+use crate::conf::{self, ConfToken};
+use crate::daemon::Rigctld;
+use crate::framing::{self, Requests};
+use crate::frequency::Frequency;
+use crate::keepalive::{Keepalive, LinkHealth};
+use crate::reconnect::{ConnectionStatus, ReconnectPolicy};
+use crate::throttle::{Limiter, Throttle};
+use crate::timeouts::{CommandClass, TimeoutOverride, Timeouts};
+use crate::trace;
+use crate::transport::{
+    StreamTransport, TcpTransport, Transport, TransportReader, TransportWriter,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     USB,
     LSB,
@@ -92,6 +110,186 @@ impl FromStr for Mode {
     }
 }
 
+impl Mode {
+    /// Get the hamlib mode bit (`RIG_MODE_*`) of the mode.
+    fn bits(&self) -> u64 {
+        match self {
+            Mode::AM => 1 << 0,
+            Mode::CW => 1 << 1,
+            Mode::USB => 1 << 2,
+            Mode::LSB => 1 << 3,
+            Mode::RTTY => 1 << 4,
+            Mode::FM => 1 << 5,
+            Mode::WFM => 1 << 6,
+            Mode::CWR => 1 << 7,
+            Mode::RTTYR => 1 << 8,
+            Mode::AMS => 1 << 9,
+            Mode::PKTLSB => 1 << 10,
+            Mode::PKTUSB => 1 << 11,
+            Mode::PKTFM => 1 << 12,
+            Mode::ECSSUSB => 1 << 13,
+            Mode::ECSSLSB => 1 << 14,
+            Mode::FAX => 1 << 15,
+            Mode::SAM => 1 << 16,
+            Mode::SAL => 1 << 17,
+            Mode::SAH => 1 << 18,
+            Mode::DSB => 1 << 19,
+        }
+    }
+}
+
+/// Passband of the rigs current mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Passband {
+    /// Keep the current passband (hamlib's `RIG_PASSBAND_NOCHANGE`, -1).
+    NoChange,
+    /// Use the rigs normal passband of the mode (hamlib's `RIG_PASSBAND_NORMAL`, 0).
+    Normal,
+    /// Use the next narrower filter than the normal one, see hamlib's `rig_passband_narrow`.
+    Narrow,
+    /// Use the next wider filter than the normal one, see hamlib's `rig_passband_wide`.
+    Wide,
+    /// Passband width (Hz).
+    Hz(u32),
+}
+
+impl Passband {
+    /// Convert a passband value as reported by `rigctld`.
+    fn from_hamlib(value: i64) -> Passband {
+        match value {
+            v if v < 0 => Passband::NoChange,
+            0 => Passband::Normal,
+            v => Passband::Hz(u32::try_from(v).unwrap_or(u32::MAX)),
+        }
+    }
+}
+
+impl fmt::Display for Passband {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Passband::NoChange => write!(f, "no change"),
+            Passband::Normal => write!(f, "normal"),
+            Passband::Narrow => write!(f, "narrow"),
+            Passband::Wide => write!(f, "wide"),
+            Passband::Hz(hz) => write!(f, "{} Hz", hz),
+        }
+    }
+}
+
+/// Scan function of the rigs internal scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanFunction {
+    /// Stop a running scan
+    STOP,
+    /// Scan all memory channels
+    MEM,
+    /// Scan all selected memory channels
+    SLCT,
+    /// Priority watch
+    PRIO,
+    /// Programmed scan between the edges of a band
+    PROG,
+    /// Delta-f scan around the current frequency
+    DELTA,
+    /// Scan all VFO frequencies
+    VFO,
+    /// Scan using a previously stored VFO frequency as base
+    PLT,
+}
+
+impl fmt::Display for ScanFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScanFunction::STOP => write!(f, "STOP"),
+            ScanFunction::MEM => write!(f, "MEM"),
+            ScanFunction::SLCT => write!(f, "SLCT"),
+            ScanFunction::PRIO => write!(f, "PRIO"),
+            ScanFunction::PROG => write!(f, "PROG"),
+            ScanFunction::DELTA => write!(f, "DELTA"),
+            ScanFunction::VFO => write!(f, "VFO"),
+            ScanFunction::PLT => write!(f, "PLT"),
+        }
+    }
+}
+
+impl FromStr for ScanFunction {
+    type Err = RigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "STOP" => Ok(ScanFunction::STOP),
+            "MEM" => Ok(ScanFunction::MEM),
+            "SLCT" => Ok(ScanFunction::SLCT),
+            "PRIO" => Ok(ScanFunction::PRIO),
+            "PROG" => Ok(ScanFunction::PROG),
+            "DELTA" => Ok(ScanFunction::DELTA),
+            "VFO" => Ok(ScanFunction::VFO),
+            "PLT" => Ok(ScanFunction::PLT),
+            _ => Err(RigError::InternalError),
+        }
+    }
+}
+
+/// VFO of the rig.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vfo {
+    VFOA,
+    VFOB,
+    VFOC,
+    Main,
+    Sub,
+    MainA,
+    MainB,
+    SubA,
+    SubB,
+    MEM,
+    TX,
+    RX,
+    /// The currently selected VFO
+    CurrVFO,
+}
+
+impl fmt::Display for Vfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Vfo::VFOA => write!(f, "VFOA"),
+            Vfo::VFOB => write!(f, "VFOB"),
+            Vfo::VFOC => write!(f, "VFOC"),
+            Vfo::Main => write!(f, "Main"),
+            Vfo::Sub => write!(f, "Sub"),
+            Vfo::MainA => write!(f, "MainA"),
+            Vfo::MainB => write!(f, "MainB"),
+            Vfo::SubA => write!(f, "SubA"),
+            Vfo::SubB => write!(f, "SubB"),
+            Vfo::MEM => write!(f, "MEM"),
+            Vfo::TX => write!(f, "TX"),
+            Vfo::RX => write!(f, "RX"),
+            Vfo::CurrVFO => write!(f, "currVFO"),
+        }
+    }
+}
+
+impl FromStr for Vfo {
+    type Err = RigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "VFOA" => Ok(Vfo::VFOA),
+            "VFOB" => Ok(Vfo::VFOB),
+            "VFOC" => Ok(Vfo::VFOC),
+            "Main" => Ok(Vfo::Main),
+            "Sub" => Ok(Vfo::Sub),
+            "MainA" => Ok(Vfo::MainA),
+            "MainB" => Ok(Vfo::MainB),
+            "SubA" => Ok(Vfo::SubA),
+            "SubB" => Ok(Vfo::SubB),
+            "MEM" => Ok(Vfo::MEM),
+            "TX" => Ok(Vfo::TX),
+            "RX" => Ok(Vfo::RX),
+            "currVFO" => Ok(Vfo::CurrVFO),
+            _ => Err(RigError::InternalError),
+        }
+    }
+}
+
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum RigError {
     /// Failed to connect to `rigctld`
@@ -110,6 +308,26 @@ pub enum RigError {
     #[error("Already connected")]
     AlreadyConnected,
 
+    /// Not connected to `rigctld`
+    #[error("Not connected")]
+    NotConnected,
+
+    /// `rigctld` rejected the password
+    #[error("Authentication failed")]
+    AuthenticationFailed,
+
+    /// `rigctld` exited before it was ready
+    #[error("Daemon exited with {0}")]
+    DaemonExited(ExitStatus),
+
+    /// Failed to parse a value
+    #[error("Failed to parse value")]
+    ParseError,
+
+    /// Invalid argument, e.g. one `rigctld` is unable to parse
+    #[error("Invalid argument")]
+    InvalidArgument,
+
     /// Internal error
     #[error("Internal error")]
     InternalError,
@@ -117,58 +335,380 @@ pub enum RigError {
 
 /// Representation of a connection to `rigctld`.
 pub struct Rig {
-    host: String,
-    port: u16,
-    reader: Option<BufReader<OwnedReadHalf>>,
-    writer: Option<OwnedWriteHalf>,
-    timeout: time::Duration,
+    transport: Box<dyn Transport>,
+    reader: Option<BufReader<TransportReader>>,
+    writer: Option<TransportWriter>,
+    timeouts: Timeouts,
+    timeout_override: Option<time::Duration>,
+    requests: Requests,
+    cache: Option<ReadCache>,
+    limiter: Option<Limiter>,
+    unsent: Vec<u8>,
+    line: Vec<u8>,
+    partial: String,
+    vfo_mode: bool,
+    vfo: Option<Vfo>,
+    password: Option<String>,
+    reconnect: Option<ReconnectPolicy>,
+    lost: bool,
+    attempts: u32,
+    status: watch::Sender<ConnectionStatus>,
+    keepalive: Option<Keepalive>,
+    health: watch::Sender<LinkHealth>,
+    latency: Option<time::Duration>,
+    failures: u32,
+    last_activity: time::Instant,
 }
 
 impl Rig {
-    /// Create a new instance of `Rig`.
+    /// Create a new instance of `Rig` connecting to `rigctld` via TCP.
     pub fn new(host: &str, port: u16) -> Rig {
+        Rig::with_transport(TcpTransport::new(host, port))
+    }
+
+    /// Create a new instance of `Rig` connecting to `rigctld` via the given transport, e.g. `UnixTransport`.
+    pub fn with_transport<T: Transport + 'static>(transport: T) -> Rig {
         Rig {
-            host: String::from(host),
-            port,
+            transport: Box::new(transport),
             reader: None,
             writer: None,
-            timeout: time::Duration::from_millis(250),
+            timeouts: Timeouts::default(),
+            timeout_override: None,
+            requests: Requests::default(),
+            cache: None,
+            limiter: None,
+            unsent: Vec::new(),
+            line: Vec::new(),
+            partial: String::new(),
+            vfo_mode: false,
+            vfo: None,
+            password: None,
+            reconnect: None,
+            lost: false,
+            attempts: 0,
+            status: watch::channel(ConnectionStatus::Disconnected).0,
+            keepalive: None,
+            health: watch::channel(LinkHealth::Lost).0,
+            latency: None,
+            failures: 0,
+            last_activity: time::Instant::now(),
         }
     }
 
+    /// Create a new instance of `Rig` communicating via an already established stream, e.g. `tokio::io::duplex`.
+    /// The stream is used on connect, afterwards a lost connection cannot be re-established.
+    pub fn from_stream<S>(stream: S) -> Rig
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        Rig::with_transport(StreamTransport::new(stream))
+    }
+
     /// Connect to a already running `rigctld`.
+    /// If a password is set, the client authenticates itself right after connecting.
+    /// Afterwards, `\chk_vfo` is used to detect whether `rigctld` runs in VFO mode.
+    /// The connection is closed again if `rigctld` does not answer `\chk_vfo`, e.g. because it is still starting up.
     pub async fn connect(&mut self) -> Result<(), RigError> {
         if self.is_connected() {
             return Err(RigError::AlreadyConnected);
         }
 
-        let constring = format!("{}:{}", self.host, self.port);
-
-        let stream = TcpStream::connect(constring)
+        let (rx, tx) = self
+            .transport
+            .connect()
             .await
             .map_err(|_| RigError::ConnectionError)?;
-        let (rx, tx) = stream.into_split();
+        self.close();
         self.reader = Some(BufReader::new(rx));
         self.writer = Some(tx);
 
+        // Failures keep the state of the connection, e.g. while reconnecting
+        if let Some(password) = self.password.clone() {
+            if let Err(e) = self.authenticate(&password).await {
+                self.close();
+                return Err(e);
+            }
+        }
+
+        // The probe fails if `rigctld` is not ready to answer commands yet
+        let response = match self.exchange(r";\chk_vfo").await {
+            Ok(response) => response,
+            Err(e) => {
+                self.close();
+                return Err(e);
+            }
+        };
+
+        // Older versions of `rigctld` may not know `\chk_vfo`, keep the configured mode in that case
+        if let Ok(vfo_mode) = Rig::parse_chk_vfo(&response) {
+            self.vfo_mode = vfo_mode;
+        }
+
+        self.lost = false;
+        self.attempts = 0;
+        self.status.send_replace(ConnectionStatus::Connected);
+
         Ok(())
     }
 
+    /// Connect to `rigctld` as soon as it is ready to answer commands, e.g. right after spawning it.
+    /// Retries to connect until `rigctld` answers the probe command `\chk_vfo` or the timeout elapses.
+    ///
+    /// # Arguments:
+    ///
+    /// * `timeout`: Time to wait for `rigctld` to get ready
+    /// * `daemon`: Spawned instance of `rigctld` to stop waiting early if the process exits, e.g. because the port is already taken
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn connect_when_ready(
+        &mut self,
+        timeout: time::Duration,
+        mut daemon: Option<&mut Rigctld>,
+    ) -> Result<(), RigError> {
+        let deadline = time::Instant::now() + timeout;
+
+        loop {
+            if let Some(status) = daemon.as_mut().and_then(|d| d.exit_status()) {
+                return Err(RigError::DaemonExited(status));
+            }
+
+            let res = match time::timeout_at(deadline, self.connect()).await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(e @ (RigError::AlreadyConnected | RigError::AuthenticationFailed))) => {
+                    return Err(e)
+                }
+                Ok(Err(e)) => Err(e),
+                Err(_) => {
+                    // The connection may already be established while the probe is outstanding
+                    self.disconnect();
+                    Err(RigError::CommunicationTimeout)
+                }
+            };
+
+            if time::Instant::now() >= deadline {
+                return res;
+            }
+            time::sleep(time::Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Connect to a already running `rigctld` that requires a password (`rigctld --password`).
+    /// Requires hamlib 4.6 or newer.
+    /// The password is kept for later calls to `connect`.
+    pub async fn connect_with_password(&mut self, password: &str) -> Result<(), RigError> {
+        self.set_password(Some(String::from(password)));
+        self.connect().await
+    }
+
+    /// Set the password used to authenticate against `rigctld` on connect.
+    /// Use `None` if `rigctld` does not require a password.
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password;
+    }
+
     /// Disconnect from `rigctld`.
     /// Returns true after disconnect. May return false in case the connection was already closed.
     pub fn disconnect(&mut self) -> bool {
+        self.lost = false;
+        self.attempts = 0;
+        self.status.send_replace(ConnectionStatus::Disconnected);
+        self.set_health(LinkHealth::Lost);
+
         if self.is_connected() {
-            self.reader = None;
-            self.writer = None;
+            self.close();
             true
         } else {
             false
         }
     }
 
+    /// Set the policy to re-establish a lost connection, `None` to disable reconnecting (default).
+    /// Commands that only query the rig (e.g. `get_frequency`) are retried transparently after reconnecting.
+    /// All other commands fail with `RigError::ConnectionLost`, since it is unknown whether they reached the rig.
+    pub fn set_reconnect_policy(&mut self, policy: Option<ReconnectPolicy>) {
+        self.reconnect = policy;
+    }
+
+    /// Get a receiver to follow the state of the connection, e.g. to show that the client is reconnecting.
+    pub fn subscribe_status(&self) -> watch::Receiver<ConnectionStatus> {
+        self.status.subscribe()
+    }
+
+    /// Set the keepalive settings, `None` to disable the keepalive (default).
+    /// The settings also determine how the link health is assessed, which is tracked with every command even without keepalive.
+    /// An overdue keepalive is sent right before the next command, thus a dead link is detected before e.g. a set command is sent.
+    /// To keep the link alive while idle, `RigHandle` sends keepalives on its own.
+    /// Without `RigHandle`, run a task which calls `keepalive` as soon as `keepalive_due` has passed.
+    pub fn set_keepalive(&mut self, keepalive: Option<Keepalive>) {
+        self.keepalive = keepalive;
+    }
+
+    /// Get a receiver to follow the health of the link, e.g. to block transmitting while the link is degraded or lost.
+    pub fn subscribe_health(&self) -> watch::Receiver<LinkHealth> {
+        self.health.subscribe()
+    }
+
+    /// Get the round-trip time of the latest successful command.
+    pub fn get_latency(&self) -> Option<time::Duration> {
+        self.latency
+    }
+
+    /// Get the number of consecutive commands that failed due to a timeout or a lost connection.
+    pub fn get_consecutive_failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Get the point in time the next keepalive is due.
+    /// Returns `None` if the keepalive is disabled or there is no connection to keep alive.
+    pub fn keepalive_due(&self) -> Option<time::Instant> {
+        let keepalive = self.keepalive.as_ref()?;
+        if self.is_connected() || (self.lost && self.reconnect.is_some()) {
+            Some(self.last_activity + keepalive.get_interval())
+        } else {
+            None
+        }
+    }
+
+    /// Send a keepalive to `rigctld` and update the link health.
+    /// The keepalive `\chk_vfo` is answered by `rigctld` itself and does not cause any traffic to the rig.
+    ///
+    /// # Result
+    ///
+    /// Returns the round-trip time of the keepalive or in case of an error the error cause.
+    /// While the connection is lost, a single attempt to re-establish it is made instead, see `set_reconnect_policy`.
+    /// The next keepalive is due one interval after the attempt, thus other commands are not blocked by a whole series of attempts.
+    pub async fn keepalive(&mut self) -> Result<time::Duration, RigError> {
+        if let (true, Some(policy)) = (self.lost, self.reconnect.clone()) {
+            let res = self.reconnect_attempt(&policy).await;
+            self.last_activity = time::Instant::now();
+            res?;
+        }
+
+        self.send_keepalive().await?;
+        self.latency.ok_or(RigError::InternalError)
+    }
+
+    /// Send a keepalive without checking whether another one is due first and without attempting to reconnect.
+    async fn send_keepalive(&mut self) -> Result<String, RigError> {
+        self.exchange(r";\chk_vfo").await
+    }
+
     /// Set communication timeout for communication with `rigctld`.
+    /// Applies to queries and set commands, slow commands like `\set_powerstat` keep their own timeout (see `set_timeouts`).
     pub fn set_communication_timeout(&mut self, timeout: time::Duration) {
-        self.timeout = timeout;
+        self.timeouts = self
+            .timeouts
+            .clone()
+            .set_class_timeout(CommandClass::Query, timeout)
+            .set_class_timeout(CommandClass::Set, timeout);
+    }
+
+    /// Set the timeouts of the command classes and individual commands.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Get the timeouts of the command classes and individual commands.
+    pub fn get_timeouts(&self) -> &Timeouts {
+        &self.timeouts
+    }
+
+    /// Override the timeout of all commands issued through the returned guard, e.g. `rig.with_timeout(d).get_frequency().await`.
+    /// The configured timeouts apply again as soon as the guard is dropped.
+    pub fn with_timeout(&mut self, timeout: time::Duration) -> TimeoutOverride<'_> {
+        TimeoutOverride::new(self, timeout)
+    }
+
+    /// Set the limits of the commands sent to `rigctld`, `None` to send commands without delay (default).
+    /// Commands are delayed until the limits allow to send them, the delay does not count towards their timeouts.
+    /// Use `RigHandle` to let commands changing the rig (e.g. PTT) overtake queued queries.
+    ///
+    /// # Arguments:
+    ///
+    /// * `throttle`: Minimum spacing and maximum rate of the commands
+    ///
+    /// # Result
+    ///
+    /// (None)
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.limiter = throttle.map(Limiter::new);
+    }
+
+    /// Get the limits of the commands sent to `rigctld`.
+    pub fn get_throttle(&self) -> Option<&Throttle> {
+        self.limiter.as_ref().map(|limiter| limiter.throttle())
+    }
+
+    /// Enable or disable the read cache.
+    /// While enabled, the values of `get_frequency`, `get_mode`, `get_vfo`, `get_ptt`, `get_split_vfo` and `get_rf_power`
+    /// are remembered for the given time to live and served without asking `rigctld`.
+    /// Successful `set_*` calls update the cache directly, so their values are served without delay as well.
+    /// Changes made at the rig itself or by other clients are only seen once the cached values are expired.
+    ///
+    /// # Arguments:
+    ///
+    /// * `ttl`: Time to live of the cached values, `None` disables the cache (default)
+    ///
+    /// # Result
+    ///
+    /// (None)
+    pub fn set_read_cache(&mut self, ttl: Option<time::Duration>) {
+        self.cache = ttl.map(ReadCache::new);
+    }
+
+    /// Forget all values of the read cache, e.g. after the rig was operated manually.
+    pub fn clear_read_cache(&mut self) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.clear();
+        }
+    }
+
+    /// Get the cached reply to a query.
+    fn cached(&self, request: &str) -> Option<Reply> {
+        self.cache.as_ref().and_then(|cache| cache.get(request))
+    }
+
+    /// Remember the reply to a query within the read cache, if enabled.
+    pub(crate) fn remember(&mut self, request: String, reply: Reply) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.insert(request, reply);
+        }
+    }
+
+    /// Forget the reply to a query, e.g. since the value is about to change.
+    /// In VFO mode the replies for a specific VFO and for `currVFO` might refer to the same VFO and are forgotten together.
+    pub(crate) fn forget(&mut self, request: &str) {
+        let vfo_mode = self.vfo_mode;
+        if let Some(cache) = self.cache.as_mut() {
+            if vfo_mode {
+                cache.remove_vfo(request);
+            } else {
+                cache.remove(request);
+            }
+        }
+    }
+
+    /// Queue several commands to be sent to `rigctld` at once, see `Batch`.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns an empty batch to queue the commands in.
+    pub fn batch(&mut self) -> Batch<'_> {
+        Batch::new(self)
+    }
+
+    /// Replace the timeout override and get the previous one.
+    pub(crate) fn replace_timeout_override(
+        &mut self,
+        timeout: Option<time::Duration>,
+    ) -> Option<time::Duration> {
+        std::mem::replace(&mut self.timeout_override, timeout)
     }
 
     /// Check if connected to rig
@@ -176,6 +716,107 @@ impl Rig {
         self.reader.is_some() && self.writer.is_some()
     }
 
+    /// Set whether `rigctld` runs in VFO mode (`rigctld --vfo`).
+    /// In VFO mode every VFO related command carries the target VFO as first argument.
+    /// The mode is detected automatically on connect, use this function only for versions of `rigctld` without `\chk_vfo`.
+    pub fn set_vfo_mode(&mut self, enabled: bool) {
+        self.vfo_mode = enabled;
+    }
+
+    /// Check if the client talks to `rigctld` in VFO mode.
+    pub fn is_vfo_mode(&self) -> bool {
+        self.vfo_mode
+    }
+
+    /// Set the default VFO targeted by VFO related commands, e.g. `get_frequency`.
+    /// Use `None` to target the currently selected VFO.
+    /// The target is only sent to `rigctld` in VFO mode, otherwise all commands act on the currently selected VFO.
+    /// Use `with_vfo` to target another VFO with individual commands.
+    pub fn set_target_vfo(&mut self, vfo: Option<Vfo>) {
+        self.vfo = vfo;
+    }
+
+    /// Target a VFO with all commands issued through the returned guard, e.g. `rig.with_vfo(Vfo::VFOB).get_frequency().await`.
+    /// The default target (see `set_target_vfo`) applies again as soon as the guard is dropped.
+    pub fn with_vfo(&mut self, vfo: Vfo) -> VfoOverride<'_> {
+        VfoOverride::new(self, vfo)
+    }
+
+    /// Get the VFO targeted by VFO related commands.
+    pub fn get_target_vfo(&self) -> Option<Vfo> {
+        self.vfo
+    }
+
+    /// Check whether `rigctld` runs in VFO mode.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns true if `rigctld` runs in VFO mode or in case of an error the error cause.
+    pub async fn chk_vfo(&mut self) -> Result<bool, RigError> {
+        let response = self.execute_command(r";\chk_vfo").await?;
+        Rig::parse_chk_vfo(&response)
+    }
+
+    /// Parse the response to `\chk_vfo`.
+    fn parse_chk_vfo(response: &str) -> Result<bool, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^(?:chk_vfo:;)?(?:ChkVFO: |CHKVFO )?([01])[;\n]?(?:RPRT 0)?$")
+                    .unwrap();
+        }
+
+        let vfo_mode = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        Ok(vfo_mode.as_str() == "1")
+    }
+
+    /// Get the currently selected VFO of the rig.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the VFO or in case of an error the error cause.
+    pub async fn get_vfo(&mut self) -> Result<Vfo, RigError> {
+        let request = Rig::get_vfo_request();
+        if let Some(Reply::Vfo(vfo)) = self.cached(&request) {
+            return Ok(vfo);
+        }
+
+        let response = self.execute_command(&request).await?;
+        let vfo = Rig::parse_get_vfo(&response)?;
+        self.remember(request, Reply::Vfo(vfo));
+        Ok(vfo)
+    }
+
+    /// Get the request of `\get_vfo`.
+    /// Unlike other VFO related commands, `rigctld` does not accept a VFO argument for `\get_vfo` even in VFO mode.
+    pub(crate) fn get_vfo_request() -> String {
+        String::from(r";\get_vfo")
+    }
+
+    /// Parse the response to `\get_vfo`.
+    pub(crate) fn parse_get_vfo(response: &str) -> Result<Vfo, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_vfo:(?: [A-Za-z]+)?;VFO: ([A-Za-z]+);RPRT 0$").unwrap();
+        }
+
+        let vfo = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        Vfo::from_str(vfo.as_str())
+    }
+
     /// Get the rigs frequency.
     ///
     /// # Arguments:
@@ -185,16 +826,30 @@ impl Rig {
     /// # Result
     ///
     /// Returns the frequency or in case of an error the error cause.
-    pub async fn get_frequency(&mut self) -> Result<u64, RigError> {
+    pub async fn get_frequency(&mut self) -> Result<Frequency, RigError> {
+        let request = format!(r";\get_freq{}", self.vfo_arg());
+        if let Some(Reply::Frequency(freq)) = self.cached(&request) {
+            return Ok(freq);
+        }
+
+        let response = self.execute_command(&request).await?;
+        let freq = Rig::parse_get_freq(&response)?;
+        self.remember(request, Reply::Frequency(freq));
+        Ok(freq)
+    }
+
+    /// Parse the response to `\get_freq`.
+    pub(crate) fn parse_get_freq(response: &str) -> Result<Frequency, RigError> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"^get_freq:;Frequency: (\d+);RPRT 0$").unwrap();
+            static ref RE: Regex =
+                Regex::new(r"^get_freq:(?: [A-Za-z]+)?;Frequency: (\d+(?:\.\d+)?);RPRT 0$")
+                    .unwrap();
         }
 
-        let response = self.execute_command(r";\get_freq").await?;
         let freq = RE
-            .captures(&response)
+            .captures(response)
             .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
-        let freq = freq.as_str().parse::<u64>().unwrap();
+        let freq = Frequency::from_str(freq.as_str())?;
 
         Ok(freq)
     }
@@ -203,23 +858,34 @@ impl Rig {
     ///
     /// # Arguments:
     ///
-    /// * `frequency`: Frequency (Hz)
+    /// * `frequency`: Frequency
     ///
     /// # Result
     ///
     /// In case of an error the causing error is returned.
-    pub async fn set_frequency(&mut self, frequency: u64) -> Result<(), RigError> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^set_freq: (\d+);RPRT 0$").unwrap();
-        }
+    pub async fn set_frequency(&mut self, frequency: Frequency) -> Result<(), RigError> {
+        let cached = format!(r";\get_freq{}", self.vfo_arg());
+        self.forget(&cached);
 
-        let request = format!(r";\set_freq {}", frequency);
+        let request = format!(r";\set_freq{} {}", self.vfo_arg(), frequency.hz());
         let response = self.execute_command(&request).await?;
+        Rig::parse_set_freq(&response, frequency)?;
+
+        self.remember(cached, Reply::Frequency(frequency));
+        Ok(())
+    }
+
+    /// Parse the response to `\set_freq` and check that the given frequency was set.
+    pub(crate) fn parse_set_freq(response: &str, frequency: Frequency) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^set_freq:(?: [A-Za-z]+)? (\d+(?:\.\d+)?);RPRT 0$").unwrap();
+        }
 
         let freq = RE
-            .captures(&response)
+            .captures(response)
             .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
-        let freq_out = freq.as_str().parse::<u64>().unwrap();
+        let freq_out = Frequency::from_str(freq.as_str())?;
 
         if freq_out == frequency {
             Ok(())
@@ -228,7 +894,7 @@ impl Rig {
         }
     }
 
-    /// Get the rigs mode.
+    /// Get the rigs tuning step.
     ///
     /// # Arguments:
     ///
@@ -236,98 +902,1401 @@ impl Rig {
     ///
     /// # Result
     ///
-    /// Returns the mode and passband or in case of an error the error cause.
-    pub async fn get_mode(&mut self) -> Result<(Mode, u16), RigError> {
+    /// Returns the tuning step (Hz) or in case of an error the error cause.
+    pub async fn get_ts(&mut self) -> Result<u64, RigError> {
         lazy_static! {
             static ref RE: Regex =
-                Regex::new(r"^get_mode:;Mode: ([A-Z]+);Passband: (\d+);RPRT 0$").unwrap();
+                Regex::new(r"^get_ts:(?: [A-Za-z]+)?;Tuning Step: (\d+);RPRT 0$").unwrap();
         }
 
-        let response = self.execute_command(r";\get_mode").await?;
-        let result = RE
+        let response = self
+            .execute_command(&format!(r";\get_ts{}", self.vfo_arg()))
+            .await?;
+        let step = RE
             .captures(&response)
-            .map_or(Err(RigError::InternalError), |c| {
-                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
-            })?;
-        let mode = Mode::from_str(result.0.as_str())?;
-        let passband = result.1.as_str().parse::<u16>().unwrap();
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let step = step.as_str().parse::<u64>().unwrap();
 
-        Ok((mode, passband))
+        Ok(step)
     }
 
-    /// Set the rigs mode.
+    /// Set the rigs tuning step.
     ///
     /// # Arguments:
     ///
-    /// * `mode`: Operating mode
-    /// * `passband`: Passband frequency (Hz)
+    /// * `step`: Tuning step (Hz)
     ///
     /// # Result
     ///
     /// In case of an error the causing error is returned.
-    pub async fn set_mode(&mut self, mode: Mode, passband: u16) -> Result<(), RigError> {
+    pub async fn set_ts(&mut self, step: u64) -> Result<(), RigError> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"^set_mode: ([A-Z]+) (\d+);RPRT 0$").unwrap();
+            static ref RE: Regex = Regex::new(r"^set_ts:(?: [A-Za-z]+)? (\d+);RPRT 0$").unwrap();
         }
 
-        let request = format!(r";\set_mode {} {}", mode, passband);
+        let request = format!(r";\set_ts{} {}", self.vfo_arg(), step);
         let response = self.execute_command(&request).await?;
 
-        let result = RE
+        let step_out = RE
             .captures(&response)
-            .map_or(Err(RigError::InternalError), |c| {
-                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
-            })?;
-        let mode_out = Mode::from_str(result.0.as_str())?;
-        let passband_out = result.1.as_str().parse::<u16>().unwrap();
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let step_out = step_out.as_str().parse::<u64>().unwrap();
 
-        if mode == mode_out && passband_out == passband {
+        if step_out == step {
             Ok(())
         } else {
             Err(RigError::InternalError)
         }
     }
 
-    /// Issue a command to rigctld and read its response.
-    async fn execute_command(&mut self, input: &str) -> Result<String, RigError> {
-        self.write_line(input).await?;
-        self.read_line(self.timeout).await
+    /// Tune the rig by a number of tuning steps.
+    ///
+    /// # Arguments:
+    ///
+    /// * `steps`: Number of tuning steps, positive values tune up, negative values tune down
+    ///
+    /// # Result
+    ///
+    /// Returns the new frequency or in case of an error the error cause.
+    pub async fn step_frequency(&mut self, steps: i64) -> Result<Frequency, RigError> {
+        let step = i64::try_from(self.get_ts().await?).map_err(|_| RigError::InternalError)?;
+        let frequency = self
+            .get_frequency()
+            .await?
+            .checked_offset(steps.saturating_mul(step))
+            .ok_or(RigError::InternalError)?;
+
+        self.set_frequency(frequency).await?;
+
+        Ok(frequency)
     }
 
-    /// Read a string from a tcp stream with timeout.
-    async fn read_line(&mut self, timeout: time::Duration) -> Result<String, RigError> {
-        let mut response = String::new();
+    /// Tune the rig one tuning step up.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the new frequency or in case of an error the error cause.
+    pub async fn step_up(&mut self) -> Result<Frequency, RigError> {
+        self.step_frequency(1).await
+    }
+
+    /// Tune the rig one tuning step down.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the new frequency or in case of an error the error cause.
+    pub async fn step_down(&mut self) -> Result<Frequency, RigError> {
+        self.step_frequency(-1).await
+    }
+
+    /// Move the rigs frequency onto the grid of the current tuning step.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the new frequency or in case of an error the error cause.
+    pub async fn snap_frequency(&mut self) -> Result<Frequency, RigError> {
+        let step = self.get_ts().await?;
+        let current = self.get_frequency().await?;
+        let frequency = current.round_to_step(step);
+
+        if frequency != current {
+            self.set_frequency(frequency).await?;
+        }
+
+        Ok(frequency)
+    }
+
+    /// Get the rigs mode.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the mode and passband or in case of an error the error cause.
+    pub async fn get_mode(&mut self) -> Result<(Mode, Passband), RigError> {
+        let request = format!(r";\get_mode{}", self.vfo_arg());
+        if let Some(Reply::Mode(mode, passband)) = self.cached(&request) {
+            return Ok((mode, passband));
+        }
+
+        let response = self.execute_command(&request).await?;
+        let (mode, passband) = Rig::parse_get_mode(&response)?;
+        self.remember(request, Reply::Mode(mode, passband));
+        Ok((mode, passband))
+    }
+
+    /// Parse the response to `\get_mode`.
+    pub(crate) fn parse_get_mode(response: &str) -> Result<(Mode, Passband), RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_mode:(?: [A-Za-z]+)?;Mode: ([A-Z]+);Passband: (-?\d+);RPRT 0$")
+                    .unwrap();
+        }
+
+        let result = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| {
+                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
+            })?;
+        let mode = Mode::from_str(result.0.as_str())?;
+        let passband = result.1.as_str().parse::<i64>().unwrap();
+
+        Ok((mode, Passband::from_hamlib(passband)))
+    }
+
+    /// Set the rigs mode.
+    ///
+    /// # Arguments:
+    ///
+    /// * `mode`: Operating mode
+    /// * `passband`: Passband of the mode, `Passband::Narrow` and `Passband::Wide` are resolved from the rigs filter list
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_mode(&mut self, mode: Mode, passband: Passband) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^set_mode:(?: [A-Za-z]+)? ([A-Z]+) (-?\d+);RPRT 0$").unwrap();
+        }
+
+        let width: i64 = match passband {
+            Passband::NoChange => -1,
+            Passband::Normal => 0,
+            Passband::Narrow => self.resolve_passband(&mode, |w, normal| w < normal).await?,
+            Passband::Wide => self.resolve_passband(&mode, |w, normal| w > normal).await?,
+            Passband::Hz(hz) => i64::from(hz),
+        };
+
+        let cached = format!(r";\get_mode{}", self.vfo_arg());
+        self.forget(&cached);
+
+        let request = format!(r";\set_mode{} {} {}", self.vfo_arg(), mode, width);
+        let response = self.execute_command(&request).await?;
+
+        let result = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| {
+                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
+            })?;
+        let mode_out = Mode::from_str(result.0.as_str())?;
+        let width_out = result.1.as_str().parse::<i64>().unwrap();
+
+        if mode == mode_out && width_out == width {
+            // The passband chosen by the rig is unknown unless given explicitly
+            if width > 0 {
+                self.remember(cached, Reply::Mode(mode, Passband::from_hamlib(width)));
+            }
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Find the passband width to use for a narrow or wide filter.
+    /// Mirrors hamlib's `rig_passband_narrow` and `rig_passband_wide`:
+    /// The first filter of the mode is the normal one, the next filter accepted by `select` is returned.
+    /// Returns 0 (normal passband) if there is no such filter.
+    async fn resolve_passband<F>(&mut self, mode: &Mode, select: F) -> Result<i64, RigError>
+    where
+        F: Fn(i64, i64) -> bool,
+    {
+        let filters: Vec<(u64, i64)> = self
+            .get_filters()
+            .await?
+            .into_iter()
+            .filter(|(modes, _)| modes & mode.bits() != 0)
+            .collect();
+
+        let width = filters.split_first().and_then(|((_, normal), others)| {
+            others
+                .iter()
+                .map(|(_, width)| *width)
+                .find(|width| select(*width, *normal))
+        });
+
+        Ok(width.unwrap_or(0))
+    }
+
+    /// Get the rigs filter list as pairs of hamlib mode bits and passband width (Hz).
+    /// The list is taken from the output of `\dump_state`.
+    async fn get_filters(&mut self) -> Result<Vec<(u64, i64)>, RigError> {
+        let response = self.execute_command(r";\dump_state").await?;
+
+        // Skip the header, the rx and tx range lists and the tuning steps to reach the filter list.
+        let mut lines = response
+            .split(['\n', ';'])
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .skip_while(|l| *l != "0 0 0 0 0 0 0")
+            .skip(1)
+            .skip_while(|l| *l != "0 0 0 0 0 0 0")
+            .skip(1)
+            .skip_while(|l| *l != "0 0")
+            .skip(1);
+
+        let mut filters = Vec::new();
+        for line in lines.by_ref() {
+            if line == "0 0" {
+                return Ok(filters);
+            }
+
+            let (modes, width) = line.split_once(' ').ok_or(RigError::InternalError)?;
+            let modes = u64::from_str_radix(modes.trim_start_matches("0x"), 16)
+                .map_err(|_| RigError::InternalError)?;
+            let width = width
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| RigError::InternalError)?;
+            filters.push((modes, width));
+        }
+
+        Err(RigError::InternalError)
+    }
+
+    /// Check if the rig is transmitting.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns true while transmitting or in case of an error the error cause.
+    pub async fn get_ptt(&mut self) -> Result<bool, RigError> {
+        let request = format!(r";\get_ptt{}", self.vfo_arg());
+        if let Some(Reply::Ptt(ptt)) = self.cached(&request) {
+            return Ok(ptt);
+        }
+
+        let response = self.execute_command(&request).await?;
+        let ptt = Rig::parse_get_ptt(&response)?;
+        self.remember(request, Reply::Ptt(ptt));
+        Ok(ptt)
+    }
+
+    /// Parse the response to `\get_ptt`.
+    pub(crate) fn parse_get_ptt(response: &str) -> Result<bool, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_ptt:(?: [A-Za-z]+)?;PTT: (\d);RPRT 0$").unwrap();
+        }
+
+        let ptt = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        // Besides 1 (on), hamlib reports 2 and 3 if transmitting via mic or data input
+        Ok(ptt.as_str() != "0")
+    }
+
+    /// Switch the rig between transmitting and receiving.
+    ///
+    /// # Arguments:
+    ///
+    /// * `ptt`: True to transmit, false to receive
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_ptt(&mut self, ptt: bool) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^set_ptt:(?: [A-Za-z]+)? (\d);RPRT 0$").unwrap();
+        }
+
+        let cached = format!(r";\get_ptt{}", self.vfo_arg());
+        self.forget(&cached);
+
+        let request = format!(r";\set_ptt{} {}", self.vfo_arg(), u8::from(ptt));
+        let response = self.execute_command(&request).await?;
+
+        let ptt_out = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        if (ptt_out.as_str() != "0") == ptt {
+            self.remember(cached, Reply::Ptt(ptt));
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Get the split state of the rig.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns whether split is enabled together with the transmit VFO or in case of an error the error cause.
+    pub async fn get_split_vfo(&mut self) -> Result<(bool, Vfo), RigError> {
+        let request = format!(r";\get_split_vfo{}", self.vfo_arg());
+        if let Some(Reply::Split(split, vfo)) = self.cached(&request) {
+            return Ok((split, vfo));
+        }
+
+        let response = self.execute_command(&request).await?;
+        let (split, vfo) = Rig::parse_get_split_vfo(&response)?;
+        self.remember(request, Reply::Split(split, vfo));
+        Ok((split, vfo))
+    }
+
+    /// Parse the response to `\get_split_vfo`.
+    pub(crate) fn parse_get_split_vfo(response: &str) -> Result<(bool, Vfo), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(
+                r"^get_split_vfo:(?: [A-Za-z]+)?;Split: ([01]);TX VFO: ([A-Za-z]+);RPRT 0$"
+            )
+            .unwrap();
+        }
+
+        let (split, vfo) = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| {
+                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
+            })?;
+
+        Ok((split.as_str() == "1", Vfo::from_str(vfo.as_str())?))
+    }
+
+    /// Control the rigs internal scan.
+    ///
+    /// # Arguments:
+    ///
+    /// * `function`: Scan function, use `ScanFunction::STOP` to stop a running scan
+    /// * `channel`: Memory channel to start the scan at (ignored by most scan functions)
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn scan(&mut self, function: ScanFunction, channel: u32) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^scan:(?: [A-Za-z]+)? ([A-Z]+) (\d+);RPRT 0$").unwrap();
+        }
+
+        // Scanning changes the frequency continuously
+        self.clear_read_cache();
+
+        let request = format!(r";\scan{} {} {}", self.vfo_arg(), function, channel);
+        let response = self.execute_command(&request).await?;
+
+        let result = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| {
+                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
+            })?;
+        let function_out = ScanFunction::from_str(result.0.as_str())?;
+        let channel_out = result.1.as_str().parse::<u32>().unwrap();
+
+        if function == function_out && channel == channel_out {
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Get the rigs clock.
+    /// Requires hamlib 4.5 or newer.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the date and time of the rigs clock or in case of an error the error cause.
+    pub async fn get_clock(&mut self) -> Result<Clock, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_clock:;\s*(?:[A-Za-z ]+: )?([0-9T:.+-]+)[;\n]RPRT 0$").unwrap();
+        }
+
+        let response = self.execute_command(r";\get_clock").await?;
+        let clock = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let clock = Clock::from_str(clock.as_str())?;
+
+        Ok(clock)
+    }
+
+    /// Set the rigs clock.
+    /// Requires hamlib 4.5 or newer.
+    ///
+    /// # Arguments:
+    ///
+    /// * `clock`: Date and time including the offset to UTC
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_clock(&mut self, clock: &Clock) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^set_clock: ([0-9T:.+-]+);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\set_clock {}", clock);
+        let response = self.execute_command(&request).await?;
+
+        let clock_out = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let clock_out = Clock::from_str(clock_out.as_str())?;
+
+        if clock_out == *clock {
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Set the rigs clock to the time of the host system.
+    /// Requires hamlib 4.5 or newer.
+    ///
+    /// # Arguments:
+    ///
+    /// * `utc_offset`: Offset of the local time the rig shall display to UTC (minutes), 0 for UTC
+    ///
+    /// # Result
+    ///
+    /// Returns the date and time the rig was set to or in case of an error the error cause.
+    pub async fn sync_clock(&mut self, utc_offset: i16) -> Result<Clock, RigError> {
+        let clock = Clock::now().with_utc_offset(utc_offset);
+        self.set_clock(&clock).await?;
+
+        Ok(clock)
+    }
+
+    /// Get the rigs RF power level.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the RF power as fraction of the maximum power (0.0..1.0) or in case of an error the error cause.
+    pub async fn get_rf_power(&mut self) -> Result<f32, RigError> {
+        let request = format!(r";\get_level{} RFPOWER", self.vfo_arg());
+        if let Some(Reply::RfPower(power)) = self.cached(&request) {
+            return Ok(power);
+        }
+
+        let response = self.execute_command(&request).await?;
+        let power = Rig::parse_get_rf_power(&response)?;
+        self.remember(request, Reply::RfPower(power));
+        Ok(power)
+    }
+
+    /// Parse the response to `\get_level RFPOWER`.
+    pub(crate) fn parse_get_rf_power(response: &str) -> Result<f32, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_level:(?: [A-Za-z]+)? RFPOWER;Level Value: ([0-9.]+);RPRT 0$")
+                    .unwrap();
+        }
+
+        let power = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let power = power.as_str().parse::<f32>().unwrap();
+
+        Ok(power)
+    }
+
+    /// Get the signal strength (S-meter) of the rig.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the signal strength in dB relative to S9 or in case of an error the error cause.
+    pub async fn get_strength(&mut self) -> Result<i32, RigError> {
+        let response = self
+            .execute_command(&format!(r";\get_level{} STRENGTH", self.vfo_arg()))
+            .await?;
+        Rig::parse_get_strength(&response)
+    }
+
+    /// Parse the response to `\get_level STRENGTH`.
+    pub(crate) fn parse_get_strength(response: &str) -> Result<i32, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_level:(?: [A-Za-z]+)? STRENGTH;Level Value: (-?\d+);RPRT 0$")
+                    .unwrap();
+        }
+
+        let strength = RE
+            .captures(response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        strength
+            .as_str()
+            .parse::<i32>()
+            .map_err(|_| RigError::InternalError)
+    }
+
+    /// Set the rigs RF power level.
+    ///
+    /// # Arguments:
+    ///
+    /// * `power`: RF power as fraction of the maximum power (0.0..1.0)
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_rf_power(&mut self, power: f32) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^set_level:(?: [A-Za-z]+)? RFPOWER ([0-9.]+);RPRT 0$").unwrap();
+        }
+
+        let cached = format!(r";\get_level{} RFPOWER", self.vfo_arg());
+        self.forget(&cached);
+
+        let power = power.clamp(0.0, 1.0);
+        let request = format!(r";\set_level{} RFPOWER {}", self.vfo_arg(), power);
+        let response = self.execute_command(&request).await?;
+
+        let power_out = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let power_out = power_out.as_str().parse::<f32>().unwrap();
+
+        if power_out == power {
+            self.remember(cached, Reply::RfPower(power));
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Convert a RF power level into milliwatts.
+    ///
+    /// # Arguments:
+    ///
+    /// * `power`: RF power as fraction of the maximum power (0.0..1.0)
+    /// * `frequency`: Frequency the power level applies to
+    /// * `mode`: Mode the power level applies to
+    ///
+    /// # Result
+    ///
+    /// Returns the RF power (mW) or in case of an error the error cause.
+    pub async fn power_to_mw(
+        &mut self,
+        power: f32,
+        frequency: Frequency,
+        mode: &Mode,
+    ) -> Result<u32, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^power2mW: [0-9.]+ \d+ [A-Z]+;Power mW: (\d+);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\power2mW {} {} {}", power, frequency.hz(), mode);
+        let response = self.execute_command(&request).await?;
+
+        let mw = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let mw = mw.as_str().parse::<u32>().unwrap();
+
+        Ok(mw)
+    }
+
+    /// Convert milliwatts into a RF power level.
+    ///
+    /// # Arguments:
+    ///
+    /// * `mw`: RF power (mW)
+    /// * `frequency`: Frequency the power applies to
+    /// * `mode`: Mode the power applies to
+    ///
+    /// # Result
+    ///
+    /// Returns the RF power as fraction of the maximum power (0.0..1.0) or in case of an error the error cause.
+    pub async fn mw_to_power(
+        &mut self,
+        mw: u32,
+        frequency: Frequency,
+        mode: &Mode,
+    ) -> Result<f32, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^mW2power: \d+ \d+ [A-Z]+;Power[^:]*: ([0-9.]+);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\mW2power {} {} {}", mw, frequency.hz(), mode);
+        let response = self.execute_command(&request).await?;
+
+        let power = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let power = power.as_str().parse::<f32>().unwrap();
+
+        Ok(power)
+    }
+
+    /// Get the rigs RF power in watts for the current frequency and mode.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the RF power (W) or in case of an error the error cause.
+    pub async fn get_rf_power_watts(&mut self) -> Result<f32, RigError> {
+        let frequency = self.get_frequency().await?;
+        let (mode, _) = self.get_mode().await?;
+        let power = self.get_rf_power().await?;
+        let mw = self.power_to_mw(power, frequency, &mode).await?;
+
+        Ok(mw as f32 / 1000.0)
+    }
+
+    /// Set the rigs RF power in watts for the current frequency and mode.
+    ///
+    /// # Arguments:
+    ///
+    /// * `watts`: RF power (W)
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_rf_power_watts(&mut self, watts: f32) -> Result<(), RigError> {
+        let frequency = self.get_frequency().await?;
+        let (mode, _) = self.get_mode().await?;
+        let mw = (watts.max(0.0) * 1000.0).round() as u32;
+        let power = self.mw_to_power(mw, frequency, &mode).await?;
+
+        self.set_rf_power(power).await
+    }
+
+    /// Check if the front panel of the rig is locked by `rigctld`.
+    /// Requires hamlib 4.5 or newer.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the lock state or in case of an error the error cause.
+    pub async fn get_lock_mode(&mut self) -> Result<bool, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_lock_mode:;(?:[^:;]+: )?([01])[;\n]RPRT 0$").unwrap();
+        }
+
+        let response = self.execute_command(r";\get_lock_mode").await?;
+        let locked = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        Ok(locked.as_str() == "1")
+    }
+
+    /// Lock or unlock the front panel of the rig.
+    /// Requires hamlib 4.5 or newer.
+    ///
+    /// # Arguments:
+    ///
+    /// * `locked`: True to lock out changes on the front panel
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_lock_mode(&mut self, locked: bool) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^set_lock_mode: ([01]);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\set_lock_mode {}", u8::from(locked));
+        let response = self.execute_command(&request).await?;
+
+        let locked_out = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+
+        if (locked_out.as_str() == "1") == locked {
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Get the twiddle timeout.
+    /// While the rig is tuned by its front panel, `rigctld` suppresses frequency changes for this duration.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the twiddle timeout (zero if disabled) or in case of an error the error cause.
+    pub async fn get_twiddle(&mut self) -> Result<time::Duration, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_twiddle:;(?:[^:;]+: )?(\d+)[;\n]RPRT 0$").unwrap();
+        }
+
+        let response = self.execute_command(r";\get_twiddle").await?;
+        let timeout = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let timeout = timeout.as_str().parse::<u64>().unwrap();
 
-        let res = time::timeout(
-            timeout,
-            self.reader.as_mut().unwrap().read_line(&mut response),
-        )
-        .await
-        .map_err(|_| RigError::CommunicationTimeout)?;
+        Ok(time::Duration::from_secs(timeout))
+    }
 
-        let _ = match res {
-            Ok(0) => {
-                self.reader = None;
-                self.writer = None;
+    /// Set the twiddle timeout.
+    ///
+    /// # Arguments:
+    ///
+    /// * `timeout`: Twiddle timeout with a resolution of seconds, zero disables the twiddle detection
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_twiddle(&mut self, timeout: time::Duration) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^set_twiddle: (\d+);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\set_twiddle {}", timeout.as_secs());
+        let response = self.execute_command(&request).await?;
+
+        let timeout_out = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let timeout_out = timeout_out.as_str().parse::<u64>().unwrap();
+
+        if timeout_out == timeout.as_secs() {
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Get the timeout of `rigctld`s internal cache.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the cache timeout (zero if disabled) or in case of an error the error cause.
+    pub async fn get_cache(&mut self) -> Result<time::Duration, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_cache:;(?:[^:;]+: )?(\d+)[;\n]RPRT 0$").unwrap();
+        }
+
+        let response = self.execute_command(r";\get_cache").await?;
+        let timeout = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let timeout = timeout.as_str().parse::<u64>().unwrap();
+
+        Ok(time::Duration::from_millis(timeout))
+    }
+
+    /// Set the timeout of `rigctld`s internal cache.
+    /// Within the timeout `rigctld` answers repeated queries from its cache instead of asking the rig.
+    ///
+    /// # Arguments:
+    ///
+    /// * `timeout`: Cache timeout with a resolution of milliseconds, zero disables the cache
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    pub async fn set_cache(&mut self, timeout: time::Duration) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^set_cache: (\d+);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\set_cache {}", timeout.as_millis());
+        let response = self.execute_command(&request).await?;
+
+        let timeout_out = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| Ok(c.get(1).unwrap()))?;
+        let timeout_out = timeout_out.as_str().parse::<u128>().unwrap();
+
+        if timeout_out == timeout.as_millis() {
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Get the value of a configuration token of the rig backend.
+    ///
+    /// # Arguments:
+    ///
+    /// * `token`: Name of the token, e.g. `write_delay`
+    ///
+    /// # Result
+    ///
+    /// Returns the value of the token or in case of an error the error cause.
+    pub async fn get_conf(&mut self, token: &str) -> Result<String, RigError> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^get_conf: (\S+);(?:[^:;]+: )?([^;]*);RPRT 0$").unwrap();
+        }
+
+        let request = format!(r";\get_conf {}", token);
+        let response = self.execute_command(&request).await?;
+
+        let result = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| {
+                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
+            })?;
+
+        if result.0.as_str() == token {
+            Ok(String::from(result.1.as_str()))
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Set the value of a configuration token of the rig backend, e.g. to adjust the serial timing.
+    ///
+    /// # Arguments:
+    ///
+    /// * `token`: Name of the token, e.g. `write_delay`
+    /// * `value`: New value of the token, without whitespace
+    ///
+    /// # Result
+    ///
+    /// In case of an error the causing error is returned.
+    /// `rigctld` splits the arguments of a command at whitespace, thus tokens or values containing whitespace are rejected as invalid.
+    pub async fn set_conf(&mut self, token: &str, value: &str) -> Result<(), RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^set_conf: (\S+) (\S+);RPRT 0$").unwrap();
+        }
+
+        let invalid = |arg: &str| arg.is_empty() || arg.contains(char::is_whitespace);
+        if invalid(token) || invalid(value) {
+            return Err(RigError::InvalidArgument);
+        }
+
+        let request = format!(r";\set_conf {} {}", token, value);
+        // A configuration parameter may affect any of the cached values
+        self.clear_read_cache();
+        let response = self.execute_command(&request).await?;
+
+        let result = RE
+            .captures(&response)
+            .map_or(Err(RigError::InternalError), |c| {
+                Ok((c.get(1).unwrap(), c.get(2).unwrap()))
+            })?;
+
+        if result.0.as_str() == token && result.1.as_str() == value {
+            Ok(())
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Get the configuration tokens available for the rig backend.
+    /// Older versions of hamlib print the list to the console of `rigctld`, in that case the list is empty.
+    ///
+    /// # Arguments:
+    ///
+    /// (None)
+    ///
+    /// # Result
+    ///
+    /// Returns the configuration tokens or in case of an error the error cause.
+    pub async fn dump_conf(&mut self) -> Result<Vec<ConfToken>, RigError> {
+        let response = self.execute_command(r";\dump_conf").await?;
+
+        if response.ends_with("RPRT 0") {
+            conf::parse_dump_conf(&response)
+        } else {
+            Err(RigError::InternalError)
+        }
+    }
+
+    /// Send raw CAT bytes to the rig and read its reply, e.g. to reach features not modelled by hamlib.
+    /// Uses `\send_cmd` respectively `\send_cmd_rx` if a terminator is given.
+    ///
+    /// # Arguments:
+    ///
+    /// * `bytes`: Rig native command, e.g. a CI-V frame or a Kenwood style ASCII command
+    /// * `terminator`: Determines how the end of the reply is detected
+    ///
+    /// # Result
+    ///
+    /// Returns the raw reply of the rig or in case of an error the error cause.
+    pub async fn send_raw_cat(
+        &mut self,
+        bytes: &[u8],
+        terminator: CatTerminator,
+    ) -> Result<Vec<u8>, RigError> {
+        let command = cat::encode_command(bytes);
+        let (name, args) = match cat::encode_terminator(&terminator) {
+            Some(t) => ("send_cmd_rx", format!("{} {}", command, t)),
+            None => ("send_cmd", command),
+        };
+
+        let request = format!(r";\{} {}", name, args);
+        // Raw CAT commands bypass hamlib, thus their effect is unknown
+        self.clear_read_cache();
+        let response = self.execute_command(&request).await?;
+
+        // The command may contain the separator itself, thus strip the known echo instead of using a regex
+        let reply = response
+            .strip_prefix(&format!("{}: {}", name, args))
+            .and_then(|r| r.strip_suffix("RPRT 0"))
+            .and_then(|r| r.strip_prefix([';', '\n']))
+            .ok_or(RigError::InternalError)?;
+        let reply = reply.strip_prefix("Reply: ").unwrap_or(reply);
+        let reply = reply.strip_suffix([';', '\n']).unwrap_or(reply);
+
+        cat::decode_reply(reply, cat::is_text(bytes))
+    }
+
+    /// Authenticate against `rigctld` with the given password.
+    async fn authenticate(&mut self, password: &str) -> Result<(), RigError> {
+        let request = format!(r";\password {}", password);
+
+        // `rigctld` either reports an error or closes the connection in case of a wrong password.
+        // A timeout is no evidence of a wrong password, e.g. while `rigctld` is still starting up, and is reported as such.
+        match self.exchange(&request).await {
+            Ok(response) if response.ends_with(";RPRT 0") => Ok(()),
+            Ok(_) | Err(RigError::ConnectionLost) => Err(RigError::AuthenticationFailed),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the VFO argument of VFO related commands including the leading space.
+    /// Outside of VFO mode the argument is empty.
+    pub(crate) fn vfo_arg(&self) -> String {
+        if self.vfo_mode {
+            format!(" {}", self.vfo.unwrap_or(Vfo::CurrVFO))
+        } else {
+            String::new()
+        }
+    }
+
+    /// Issue a command to rigctld and read its response.
+    async fn execute_command(&mut self, input: &str) -> Result<String, RigError> {
+        let mut responses = self.execute_commands(&[input]).await?;
+        responses.pop().unwrap_or(Err(RigError::InternalError))
+    }
+
+    /// Issue several commands to rigctld at once and read their responses in order.
+    /// The outer result reports failures of the link, the inner results responses lost in between.
+    /// With the `tracing` feature enabled, the commands are executed within a span named after the commands.
+    pub(crate) async fn execute_commands(
+        &mut self,
+        inputs: &[&str],
+    ) -> Result<Vec<Result<String, RigError>>, RigError> {
+        #[cfg(feature = "tracing")]
+        let span = {
+            let commands: Vec<_> = inputs.iter().map(|i| trace::redact(i)).collect();
+            tracing::debug_span!("rigctld", command = %commands.join(" "))
+        };
+
+        let res = async {
+            // Detect a dead link before sending the commands
+            if self
+                .keepalive_due()
+                .is_some_and(|due| due <= time::Instant::now())
+            {
+                // A lost connection is re-established before the commands are sent, if enabled
+                if let Err(e @ RigError::ConnectionLost) = self.send_keepalive().await {
+                    if self.reconnect.is_none() {
+                        return Err(e);
+                    }
+                }
+            }
+
+            self.execute_with_reconnect(inputs).await
+        };
+
+        #[cfg(feature = "tracing")]
+        let res = tracing::Instrument::instrument(res, span);
+
+        res.await
+    }
+
+    /// Issue commands to rigctld and read their responses.
+    /// Re-establishes a lost connection according to the reconnect policy.
+    async fn execute_with_reconnect(
+        &mut self,
+        inputs: &[&str],
+    ) -> Result<Vec<Result<String, RigError>>, RigError> {
+        let policy = match self.reconnect.clone() {
+            Some(policy) => policy,
+            None => return self.exchange_all(inputs).await,
+        };
+
+        if self.lost {
+            self.reconnect(&policy).await?;
+            return self.exchange_all(inputs).await;
+        }
+
+        match self.exchange_all(inputs).await {
+            Err(RigError::ConnectionLost) => {
+                self.reconnect(&policy).await?;
+                if inputs.iter().all(|input| framing::is_query(input)) {
+                    self.exchange_all(inputs).await
+                } else {
+                    Err(RigError::ConnectionLost)
+                }
+            }
+            res => res,
+        }
+    }
+
+    /// Re-establish a lost connection to `rigctld`.
+    async fn reconnect(&mut self, policy: &ReconnectPolicy) -> Result<(), RigError> {
+        while self.lost {
+            let attempt = self.attempts + 1;
+            if policy.allows_attempt(attempt) {
+                self.status
+                    .send_replace(ConnectionStatus::Reconnecting { attempt });
+                time::sleep(policy.delay(attempt)).await;
+            }
+
+            if self.reconnect_attempt(policy).await.is_ok() {
+                return Ok(());
+            }
+        }
+
+        Err(RigError::ConnectionLost)
+    }
+
+    /// Make the next attempt to re-establish a lost connection.
+    /// Gives up if the policy allows no further attempt or the authentication fails.
+    async fn reconnect_attempt(&mut self, policy: &ReconnectPolicy) -> Result<(), RigError> {
+        let attempt = self.attempts + 1;
+        if !policy.allows_attempt(attempt) {
+            self.give_up();
+            return Err(RigError::ConnectionLost);
+        }
+
+        self.attempts = attempt;
+        self.status.send_if_modified(|status| {
+            let modified = *status != ConnectionStatus::Reconnecting { attempt };
+            *status = ConnectionStatus::Reconnecting { attempt };
+            modified
+        });
+        self.close();
+        match self.connect().await {
+            Ok(()) => Ok(()),
+            Err(RigError::AuthenticationFailed) => {
+                self.give_up();
                 Err(RigError::ConnectionLost)
             }
+            Err(_) => Err(RigError::ConnectionLost),
+        }
+    }
+
+    /// Stop attempting to re-establish a lost connection.
+    fn give_up(&mut self) {
+        self.lost = false;
+        self.attempts = 0;
+        self.status.send_replace(ConnectionStatus::Disconnected);
+    }
+
+    /// Mark the connection as lost.
+    fn connection_lost(&mut self) -> RigError {
+        self.close();
+        self.lost = true;
+        self.status.send_replace(ConnectionStatus::Disconnected);
+        self.set_health(LinkHealth::Lost);
+        RigError::ConnectionLost
+    }
+
+    /// Drop the connection together with all requests and data in transit.
+    fn close(&mut self) {
+        self.reader = None;
+        self.writer = None;
+        self.requests.clear();
+        self.clear_read_cache();
+        self.unsent.clear();
+        self.line.clear();
+        self.partial.clear();
+    }
+
+    /// Publish the health of the link, receivers are only notified about changes.
+    fn set_health(&mut self, health: LinkHealth) {
+        self.health.send_if_modified(|h| {
+            let modified = *h != health;
+            *h = health;
+            modified
+        });
+    }
+
+    /// Write a command and read its response, without any attempt to reconnect.
+    async fn exchange(&mut self, input: &str) -> Result<String, RigError> {
+        let mut responses = self.exchange_all(&[input]).await?;
+        responses.pop().unwrap_or(Err(RigError::InternalError))
+    }
+
+    /// Write commands in a single write and read their responses, without any attempt to reconnect.
+    /// Tracks the round-trip time and failures of the commands to assess the link health.
+    ///
+    /// The exchange is cancellation safe: If the future is dropped or times out, the requests are kept as outstanding.
+    /// Their responses are discarded as soon as they arrive during one of the following exchanges.
+    async fn exchange_all(
+        &mut self,
+        inputs: &[&str],
+    ) -> Result<Vec<Result<String, RigError>>, RigError> {
+        if !self.is_connected() {
+            return Err(RigError::NotConnected);
+        }
+
+        // Commands exceeding the maximum rate of the throttle are written in several chunks
+        let chunk = self
+            .limiter
+            .as_ref()
+            .and_then(|l| l.max_chunk())
+            .unwrap_or(inputs.len())
+            .max(1);
+        let mut responses = Vec::with_capacity(inputs.len());
+        for inputs in inputs.chunks(chunk) {
+            responses.extend(self.exchange_chunk(inputs).await?);
+        }
+
+        Ok(responses)
+    }
+
+    /// Write commands in a single write and read their responses, see `exchange_all`.
+    async fn exchange_chunk(
+        &mut self,
+        inputs: &[&str],
+    ) -> Result<Vec<Result<String, RigError>>, RigError> {
+        let count = inputs.len();
+        if let Some(slot) = self.limiter.as_ref().and_then(|l| l.next_slot(count)) {
+            trace::event!(
+                tracing::Level::TRACE,
+                delay_us = slot
+                    .saturating_duration_since(time::Instant::now())
+                    .as_micros() as u64,
+                "Throttled"
+            );
+            time::sleep_until(slot).await;
+        }
+
+        // The responses arrive one after another, thus the timeouts of the commands add up
+        let start = time::Instant::now();
+        if let Some(limiter) = self.limiter.as_mut() {
+            limiter.record(start, inputs.len());
+        }
+        let timeout = self
+            .timeout_override
+            .unwrap_or_else(|| inputs.iter().map(|input| self.timeouts.get(input)).sum());
+
+        self.requests.push(inputs);
+        let res = match self.write_lines(inputs).await {
+            Ok(()) => self.read_own_responses(inputs.len(), timeout).await,
+            Err(e) => Err(e),
+        };
+        self.last_activity = time::Instant::now();
+
+        let latency = self.last_activity - start;
+        #[cfg(feature = "tracing")]
+        match &res {
+            Ok(responses) => tracing::debug!(
+                responses = ?responses
+                    .iter()
+                    .map(|r| r.as_deref().map(trace::redact))
+                    .collect::<Vec<_>>(),
+                latency_us = latency.as_micros() as u64,
+                "Command succeeded"
+            ),
+            Err(e) => tracing::warn!(
+                error = %e,
+                latency_us = latency.as_micros() as u64,
+                "Command failed"
+            ),
+        }
+
+        match res {
+            Ok(_) => {
+                self.latency = Some(latency);
+                self.failures = 0;
+            }
+            Err(RigError::CommunicationTimeout) => self.failures += 1,
+            // The health of a lost connection is already reported as lost
+            Err(RigError::ConnectionLost) => {
+                self.failures += 1;
+                return res;
+            }
+            Err(_) => return res,
+        }
+
+        let keepalive = self.keepalive.clone().unwrap_or_default();
+        self.set_health(keepalive.assess(self.latency, self.failures));
+
+        res
+    }
+
+    /// Read the responses to the latest `count` requests, stale responses are discarded.
+    /// Responses found to be lost are reported as timed out.
+    async fn read_own_responses(
+        &mut self,
+        count: usize,
+        timeout: time::Duration,
+    ) -> Result<Vec<Result<String, RigError>>, RigError> {
+        let deadline = time::Instant::now() + timeout;
+        let mut responses = Vec::with_capacity(count);
+
+        while responses.len() < count {
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            let response = self.read_response(remaining).await?;
+
+            // The own requests are the latest ones, thus the last to be completed
+            self.requests.complete(&response);
+            let answered = count - self.requests.outstanding().min(count);
+            if answered <= responses.len() {
+                trace::event!(tracing::Level::DEBUG, response = %trace::redact(&response), "Discarded stale response");
+                continue;
+            }
+
+            responses.resize_with(answered - 1, || Err(RigError::CommunicationTimeout));
+            responses.push(Ok(response));
+        }
+
+        Ok(responses)
+    }
+
+    /// Read a complete response of the extended response protocol.
+    /// Most responses consist of a single line, others (e.g. `\dump_state`) span multiple lines.
+    /// In any case the response ends with a line terminated by the return code `RPRT x`.
+    /// The lines of multi-line responses are joined by '\n'.
+    /// The only exception is the plain `CHKVFO x` some versions of `rigctld` send in response to `\chk_vfo`.
+    async fn read_response(&mut self, timeout: time::Duration) -> Result<String, RigError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"RPRT -?\d+$").unwrap();
+        }
+
+        // Lines already read by a cancelled call are kept within `partial`
+        let deadline = time::Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            let line = self.read_line(remaining).await?;
+            if !self.partial.is_empty() {
+                self.partial.push('\n');
+            }
+            self.partial.push_str(&line);
+
+            if RE.is_match(&self.partial) || self.partial.starts_with("CHKVFO ") {
+                return Ok(std::mem::take(&mut self.partial));
+            }
+        }
+    }
+
+    /// Read a string from a tcp stream with timeout.
+    /// Bytes already read by a cancelled call are kept within `line`.
+    async fn read_line(&mut self, timeout: time::Duration) -> Result<String, RigError> {
+        let reader = self.reader.as_mut().ok_or(RigError::NotConnected)?;
+        let res = time::timeout(timeout, reader.read_until(b'\n', &mut self.line))
+            .await
+            .map_err(|_| RigError::CommunicationTimeout)?;
+
+        let _ = match res {
+            Ok(0) => Err(self.connection_lost()),
             Err(_) => Err(RigError::InternalError),
             Ok(num) => Ok(num),
         }?;
 
-        response = String::from(response.trim_end());
+        let response = String::from(String::from_utf8_lossy(&self.line).trim_end());
+        self.line.clear();
+        trace::event!(tracing::Level::TRACE, line = %trace::redact(&response), "Read line");
 
         Ok(response)
     }
 
-    /// Write a string to a tcp stream.
-    /// Function appends '\n' to the given string before sending it.
-    async fn write_line(&mut self, data: &str) -> Result<(), RigError> {
-        self.writer
-            .as_mut()
-            .unwrap()
-            .write_all(format!("{}\n", data).as_bytes())
-            .await
-            .map_err(|_| RigError::InternalError)
+    /// Write strings to a tcp stream, as far as possible with a single write.
+    /// Function appends '\n' to each of the given strings before sending them.
+    /// Bytes not sent by a cancelled call are kept within `unsent` and sent first, so lines are never interleaved.
+    async fn write_lines(&mut self, lines: &[&str]) -> Result<(), RigError> {
+        let writer = self.writer.as_mut().ok_or(RigError::NotConnected)?;
+
+        for data in lines {
+            trace::event!(tracing::Level::TRACE, line = %trace::redact(data), "Write line");
+            self.unsent.extend_from_slice(data.as_bytes());
+            self.unsent.push(b'\n');
+        }
+
+        while !self.unsent.is_empty() {
+            match writer.write(&self.unsent).await {
+                Ok(0) | Err(_) => return Err(self.connection_lost()),
+                Ok(num) => {
+                    self.unsent.drain(..num);
+                }
+            }
+        }
+
+        match writer.flush().await {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.connection_lost()),
+        }
+    }
+}
+
+/// `Rig` targeting a VFO other than the default one, see `Rig::with_vfo`.
+/// The previous target is restored on drop.
+pub struct VfoOverride<'a> {
+    rig: &'a mut Rig,
+    previous: Option<Vfo>,
+}
+
+/// VfoOverride implementation.
+impl<'a> VfoOverride<'a> {
+    fn new(rig: &'a mut Rig, vfo: Vfo) -> VfoOverride<'a> {
+        let previous = rig.vfo.replace(vfo);
+        VfoOverride { rig, previous }
+    }
+}
+
+impl Deref for VfoOverride<'_> {
+    type Target = Rig;
+
+    fn deref(&self) -> &Rig {
+        self.rig
+    }
+}
+
+impl DerefMut for VfoOverride<'_> {
+    fn deref_mut(&mut self) -> &mut Rig {
+        self.rig
+    }
+}
+
+impl Drop for VfoOverride<'_> {
+    fn drop(&mut self) {
+        self.rig.vfo = self.previous;
     }
 }
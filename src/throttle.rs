@@ -0,0 +1,169 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+
+use tokio::time;
+
+/// Limits of the commands sent to `rigctld`, e.g. for rigs with a slow CAT interface.
+///
+/// Every command is delayed until both the minimum spacing to the previous command has passed
+/// and the number of commands within the rate period stays below the maximum.
+/// The commands of a `Batch` are written at once, they count as one command for the spacing and individually for the rate.
+/// Batches exceeding the maximum rate are split into several writes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Throttle {
+    min_spacing: time::Duration,
+    max_rate: Option<(u32, time::Duration)>,
+}
+
+/// Throttle implementation.
+impl Throttle {
+    /// Set the minimum time between two commands, defaults to none.
+    pub fn set_min_spacing(mut self, spacing: time::Duration) -> Throttle {
+        self.min_spacing = spacing;
+        self
+    }
+
+    /// Set the maximum number of commands within a period, e.g. 10 commands per second, defaults to no limit.
+    pub fn set_max_rate(mut self, commands: u32, period: time::Duration) -> Throttle {
+        self.max_rate = Some((commands.max(1), period));
+        self
+    }
+
+    /// Get the minimum time between two commands.
+    pub fn get_min_spacing(&self) -> time::Duration {
+        self.min_spacing
+    }
+
+    /// Get the maximum number of commands within a period.
+    pub fn get_max_rate(&self) -> Option<(u32, time::Duration)> {
+        self.max_rate
+    }
+}
+
+/// State of a `Throttle`, tracks the points in time commands were sent at.
+#[derive(Debug, Clone)]
+pub(crate) struct Limiter {
+    throttle: Throttle,
+    sent: VecDeque<time::Instant>,
+}
+
+/// Limiter implementation.
+impl Limiter {
+    pub(crate) fn new(throttle: Throttle) -> Limiter {
+        Limiter {
+            throttle,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// Get the settings of the limiter.
+    pub(crate) fn throttle(&self) -> &Throttle {
+        &self.throttle
+    }
+
+    /// Get the maximum number of commands which may be written at once.
+    pub(crate) fn max_chunk(&self) -> Option<usize> {
+        self.throttle
+            .max_rate
+            .map(|(commands, _)| commands as usize)
+    }
+
+    /// Get the earliest point in time the next `count` commands may be sent at, at most `max_chunk` commands.
+    pub(crate) fn next_slot(&self, count: usize) -> Option<time::Instant> {
+        let spacing = self
+            .sent
+            .back()
+            .map(|last| *last + self.throttle.min_spacing);
+        let rate = self.throttle.max_rate.and_then(|(commands, period)| {
+            let pending = self.sent.len() + count.min(commands as usize);
+            (pending > commands as usize)
+                .then(|| self.sent[pending - commands as usize - 1] + period)
+        });
+
+        spacing.max(rate)
+    }
+
+    /// Record commands sent at the given point in time.
+    pub(crate) fn record(&mut self, at: time::Instant, commands: usize) {
+        for _ in 0..commands {
+            self.sent.push_back(at);
+        }
+
+        // Only the latest commands within the rate period are relevant
+        let keep = self
+            .throttle
+            .max_rate
+            .map_or(1, |(commands, _)| commands as usize);
+        while self.sent.len() > keep {
+            self.sent.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spacing() {
+        let mut limiter =
+            Limiter::new(Throttle::default().set_min_spacing(time::Duration::from_millis(50)));
+        let start = time::Instant::now();
+
+        assert_eq!(limiter.next_slot(1), None);
+        limiter.record(start, 1);
+        assert_eq!(
+            limiter.next_slot(1),
+            Some(start + time::Duration::from_millis(50))
+        );
+    }
+
+    #[test]
+    fn rate() {
+        let mut limiter =
+            Limiter::new(Throttle::default().set_max_rate(3, time::Duration::from_secs(1)));
+        let start = time::Instant::now();
+
+        limiter.record(start, 2);
+        assert_eq!(limiter.next_slot(1), Some(start));
+        limiter.record(start + time::Duration::from_millis(100), 1);
+        assert_eq!(
+            limiter.next_slot(1),
+            Some(start + time::Duration::from_secs(1))
+        );
+
+        // A batch counts as several commands
+        limiter.record(start + time::Duration::from_secs(1), 3);
+        assert_eq!(
+            limiter.next_slot(1),
+            Some(start + time::Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn batches() {
+        let mut limiter =
+            Limiter::new(Throttle::default().set_max_rate(3, time::Duration::from_secs(1)));
+        let start = time::Instant::now();
+        assert_eq!(limiter.max_chunk(), Some(3));
+
+        // A batch waits until enough commands left the rate period
+        limiter.record(start, 1);
+        limiter.record(start + time::Duration::from_millis(100), 1);
+        assert_eq!(
+            limiter.next_slot(1),
+            Some(start + time::Duration::from_millis(100))
+        );
+        assert_eq!(
+            limiter.next_slot(2),
+            Some(start + time::Duration::from_secs(1))
+        );
+        assert_eq!(
+            limiter.next_slot(3),
+            Some(start + time::Duration::from_millis(1100))
+        );
+    }
+}
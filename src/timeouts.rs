@@ -0,0 +1,182 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+use tokio::time;
+
+use crate::framing::{command_name, is_query};
+use crate::rig::Rig;
+
+/// Commands known to take longer than others, e.g. because the rig powers up or sends morse code.
+const SLOW_COMMANDS: [&str; 9] = [
+    "set_powerstat",
+    "send_morse",
+    "stop_morse",
+    "wait_morse",
+    "send_voice_mem",
+    "vfo_op",
+    "dump_caps",
+    "dump_state",
+    "dump_conf",
+];
+
+/// Class of a command, determines its default timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandClass {
+    /// Commands only querying the rig, e.g. `\get_freq`
+    Query,
+    /// Commands changing the state of the rig, e.g. `\set_freq`
+    Set,
+    /// Commands known to be slow, e.g. `\set_powerstat` or `\dump_caps`
+    Slow,
+}
+
+/// CommandClass implementation.
+impl CommandClass {
+    /// Get the class of a command, e.g. `get_freq` or `;\get_freq VFOA`.
+    pub fn of(command: &str) -> CommandClass {
+        let name = command_name(command);
+
+        if SLOW_COMMANDS.contains(&name) {
+            CommandClass::Slow
+        } else if is_query(name) {
+            CommandClass::Query
+        } else {
+            CommandClass::Set
+        }
+    }
+}
+
+/// Timeouts of the commands sent to `rigctld`.
+///
+/// Each command class has its own timeout, which may be overridden for individual commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeouts {
+    query: time::Duration,
+    set: time::Duration,
+    slow: time::Duration,
+    commands: HashMap<String, time::Duration>,
+}
+
+impl Default for Timeouts {
+    /// Get default timeouts.
+    /// Queries and set commands time out after 250 ms, slow commands after 10 s.
+    fn default() -> Self {
+        Timeouts {
+            query: time::Duration::from_millis(250),
+            set: time::Duration::from_millis(250),
+            slow: time::Duration::from_secs(10),
+            commands: HashMap::new(),
+        }
+    }
+}
+
+/// Timeouts implementation.
+impl Timeouts {
+    /// Set the timeout of a command class.
+    pub fn set_class_timeout(mut self, class: CommandClass, timeout: time::Duration) -> Timeouts {
+        match class {
+            CommandClass::Query => self.query = timeout,
+            CommandClass::Set => self.set = timeout,
+            CommandClass::Slow => self.slow = timeout,
+        }
+        self
+    }
+
+    /// Set the timeout of an individual command, e.g. `set_func` while using the antenna tuner.
+    pub fn set_command_timeout(mut self, command: &str, timeout: time::Duration) -> Timeouts {
+        self.commands
+            .insert(String::from(command_name(command)), timeout);
+        self
+    }
+
+    /// Get the timeout of a command class.
+    pub fn get_class_timeout(&self, class: CommandClass) -> time::Duration {
+        match class {
+            CommandClass::Query => self.query,
+            CommandClass::Set => self.set,
+            CommandClass::Slow => self.slow,
+        }
+    }
+
+    /// Get the timeout of a command, e.g. `get_freq` or `;\get_freq VFOA`.
+    pub fn get(&self, command: &str) -> time::Duration {
+        self.commands
+            .get(command_name(command))
+            .copied()
+            .unwrap_or_else(|| self.get_class_timeout(CommandClass::of(command)))
+    }
+}
+
+/// `Rig` with a timeout overriding the configured ones, see `Rig::with_timeout`.
+/// The previous timeout is restored on drop.
+pub struct TimeoutOverride<'a> {
+    rig: &'a mut Rig,
+    previous: Option<time::Duration>,
+}
+
+/// TimeoutOverride implementation.
+impl<'a> TimeoutOverride<'a> {
+    pub(crate) fn new(rig: &'a mut Rig, timeout: time::Duration) -> TimeoutOverride<'a> {
+        let previous = rig.replace_timeout_override(Some(timeout));
+        TimeoutOverride { rig, previous }
+    }
+}
+
+impl Deref for TimeoutOverride<'_> {
+    type Target = Rig;
+
+    fn deref(&self) -> &Rig {
+        self.rig
+    }
+}
+
+impl DerefMut for TimeoutOverride<'_> {
+    fn deref_mut(&mut self) -> &mut Rig {
+        self.rig
+    }
+}
+
+impl Drop for TimeoutOverride<'_> {
+    fn drop(&mut self) {
+        self.rig.replace_timeout_override(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify() {
+        assert_eq!(CommandClass::of(r";\get_freq VFOA"), CommandClass::Query);
+        assert_eq!(CommandClass::of(r";\chk_vfo"), CommandClass::Query);
+        assert_eq!(CommandClass::of(r";\set_freq 7074000"), CommandClass::Set);
+        assert_eq!(CommandClass::of(r";\set_powerstat 1"), CommandClass::Slow);
+        assert_eq!(CommandClass::of("dump_caps"), CommandClass::Slow);
+    }
+
+    #[test]
+    fn timeouts() {
+        let timeouts = Timeouts::default()
+            .set_class_timeout(CommandClass::Query, time::Duration::from_millis(100))
+            .set_command_timeout(r"\set_func", time::Duration::from_secs(30));
+
+        assert_eq!(
+            timeouts.get(r";\get_level RFPOWER"),
+            time::Duration::from_millis(100)
+        );
+        assert_eq!(
+            timeouts.get(r";\set_freq 7074000"),
+            time::Duration::from_millis(250)
+        );
+        assert_eq!(timeouts.get(r";\dump_state"), time::Duration::from_secs(10));
+        assert_eq!(
+            timeouts.get(r";\set_func TUNER 1"),
+            time::Duration::from_secs(30)
+        );
+    }
+}
@@ -0,0 +1,46 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[cfg(feature = "tracing")]
+use std::borrow::Cow;
+
+/// Emit a `tracing` event, expands to nothing without the `tracing` feature.
+macro_rules! event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!($($arg)*);
+    };
+}
+
+pub(crate) use event;
+
+/// Redact the password from a command sent to or a response received from `rigctld`.
+/// Only the return code of the response is kept, e.g. `password <redacted>;RPRT 0`.
+#[cfg(feature = "tracing")]
+pub(crate) fn redact(line: &str) -> Cow<'_, str> {
+    if line
+        .trim_start_matches([';', '+', '\\'])
+        .starts_with("password")
+    {
+        let rprt = line.find("RPRT").map_or("", |i| &line[i..]);
+        Cow::Owned(format!("password <redacted>;{}", rprt))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_password() {
+        assert_eq!(redact(r";\password secret"), "password <redacted>;");
+        assert_eq!(
+            redact("password: secret;RPRT 0"),
+            "password <redacted>;RPRT 0"
+        );
+        assert_eq!(redact(r";\get_freq"), r";\get_freq");
+    }
+}
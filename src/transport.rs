@@ -0,0 +1,127 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use tokio::io::{self, AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+/// Boxed future returned by a `Transport` or by commands executed through a `RigHandle`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Read half of a connection to `rigctld`.
+pub type TransportReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Write half of a connection to `rigctld`.
+pub type TransportWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Transport used to connect to `rigctld`, e.g. TCP (default), a Unix socket or a tunnel.
+///
+/// `Rig` opens a new connection on every connect, including the attempts to re-establish a lost connection.
+pub trait Transport: Send {
+    /// Open a new connection to `rigctld`.
+    fn connect(&mut self) -> BoxFuture<'_, io::Result<(TransportReader, TransportWriter)>>;
+}
+
+/// Connection to `rigctld` via TCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpTransport {
+    host: String,
+    port: u16,
+}
+
+/// TcpTransport implementation.
+impl TcpTransport {
+    /// Create a new TCP transport to the given host and port.
+    pub fn new(host: &str, port: u16) -> TcpTransport {
+        TcpTransport {
+            host: String::from(host),
+            port,
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn connect(&mut self) -> BoxFuture<'_, io::Result<(TransportReader, TransportWriter)>> {
+        Box::pin(async move {
+            let stream = TcpStream::connect(format!("{}:{}", self.host, self.port)).await?;
+            let (rx, tx) = stream.into_split();
+            Ok((
+                Box::new(rx) as TransportReader,
+                Box::new(tx) as TransportWriter,
+            ))
+        })
+    }
+}
+
+/// Connection to `rigctld` via a Unix socket, e.g. one forwarded by `ssh -L`.
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixTransport {
+    path: std::path::PathBuf,
+}
+
+/// UnixTransport implementation.
+#[cfg(unix)]
+impl UnixTransport {
+    /// Create a new transport to the Unix socket at the given path.
+    pub fn new<P: Into<std::path::PathBuf>>(path: P) -> UnixTransport {
+        UnixTransport { path: path.into() }
+    }
+}
+
+#[cfg(unix)]
+impl Transport for UnixTransport {
+    fn connect(&mut self) -> BoxFuture<'_, io::Result<(TransportReader, TransportWriter)>> {
+        Box::pin(async move {
+            let stream = tokio::net::UnixStream::connect(&self.path).await?;
+            let (rx, tx) = stream.into_split();
+            Ok((
+                Box::new(rx) as TransportReader,
+                Box::new(tx) as TransportWriter,
+            ))
+        })
+    }
+}
+
+/// Connection to `rigctld` via an already established stream, e.g. an SSH channel or `tokio::io::duplex`.
+///
+/// The stream can only be connected once, thus a lost connection cannot be re-established.
+pub struct StreamTransport<S> {
+    stream: Option<S>,
+}
+
+/// StreamTransport implementation.
+impl<S> StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    /// Create a new transport from an established stream.
+    pub fn new(stream: S) -> StreamTransport<S> {
+        StreamTransport {
+            stream: Some(stream),
+        }
+    }
+}
+
+impl<S> Transport for StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    fn connect(&mut self) -> BoxFuture<'_, io::Result<(TransportReader, TransportWriter)>> {
+        let stream = self.stream.take();
+
+        Box::pin(async move {
+            let stream = stream.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotConnected, "Stream already used")
+            })?;
+            let (rx, tx) = io::split(stream);
+            Ok((
+                Box::new(rx) as TransportReader,
+                Box::new(tx) as TransportWriter,
+            ))
+        })
+    }
+}
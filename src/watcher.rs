@@ -0,0 +1,223 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use tokio::sync::mpsc;
+use tokio::time;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+
+use crate::batch::Reply;
+use crate::frequency::Frequency;
+use crate::handle::RigHandle;
+use crate::rig::{Mode, Passband, RigError, Vfo};
+
+/// Change of the rigs state detected by a `RigWatcher`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RigEvent {
+    /// The frequency changed
+    FrequencyChanged(Frequency),
+    /// The mode or passband changed
+    ModeChanged(Mode, Passband),
+    /// Another VFO was selected
+    VfoChanged(Vfo),
+    /// The rig started (true) or stopped (false) transmitting
+    PttChanged(bool),
+    /// Split was enabled or disabled or the transmit VFO changed
+    SplitChanged { enabled: bool, tx_vfo: Vfo },
+}
+
+/// Number of events that may be buffered before polling pauses.
+const BUFFER_SIZE: usize = 16;
+
+/// Watches the state of the rig by polling it and reports changes as a stream of events.
+///
+/// The first poll reports the initial state of the rig, i.e. there is one event for each watched value.
+/// Afterwards an event is only emitted if the value differs from the previous poll.
+#[derive(Clone)]
+pub struct RigWatcher {
+    handle: RigHandle,
+    interval: time::Duration,
+    frequency: bool,
+    mode: bool,
+    vfo: bool,
+    ptt: bool,
+    split: bool,
+}
+
+/// Values reported by the previous poll.
+#[derive(Default)]
+struct State {
+    frequency: Option<Frequency>,
+    mode: Option<(Mode, Passband)>,
+    vfo: Option<Vfo>,
+    ptt: Option<bool>,
+    split: Option<(bool, Vfo)>,
+}
+
+/// RigWatcher implementation.
+impl RigWatcher {
+    /// Create a new watcher polling frequency, mode, VFO, PTT and split every 250 ms.
+    pub fn new(handle: RigHandle) -> RigWatcher {
+        RigWatcher {
+            handle,
+            interval: time::Duration::from_millis(250),
+            frequency: true,
+            mode: true,
+            vfo: true,
+            ptt: true,
+            split: true,
+        }
+    }
+
+    /// Set the interval between two polls.
+    pub fn set_interval(mut self, interval: time::Duration) -> RigWatcher {
+        self.interval = interval;
+        self
+    }
+
+    /// Set whether to watch the frequency.
+    pub fn set_watch_frequency(mut self, enabled: bool) -> RigWatcher {
+        self.frequency = enabled;
+        self
+    }
+
+    /// Set whether to watch the mode and passband.
+    pub fn set_watch_mode(mut self, enabled: bool) -> RigWatcher {
+        self.mode = enabled;
+        self
+    }
+
+    /// Set whether to watch the selected VFO.
+    pub fn set_watch_vfo(mut self, enabled: bool) -> RigWatcher {
+        self.vfo = enabled;
+        self
+    }
+
+    /// Set whether to watch the PTT.
+    pub fn set_watch_ptt(mut self, enabled: bool) -> RigWatcher {
+        self.ptt = enabled;
+        self
+    }
+
+    /// Set whether to watch the split state.
+    pub fn set_watch_split(mut self, enabled: bool) -> RigWatcher {
+        self.split = enabled;
+        self
+    }
+
+    /// Start polling in a background task and get the stream of events.
+    /// Failed polls are reported as errors, polling continues afterwards.
+    /// Disable values the rig does not support to avoid repeated errors.
+    /// Polling stops as soon as the stream is dropped or the connection task of the handle ends.
+    /// Must be called within a tokio runtime.
+    pub fn spawn(self) -> impl Stream<Item = Result<RigEvent, RigError>> {
+        let (events, stream) = mpsc::channel(BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(self.interval);
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            let mut state = State::default();
+
+            loop {
+                interval.tick().await;
+                if events.is_closed() || !self.handle.is_alive() {
+                    break;
+                }
+
+                for event in self.poll(&mut state).await {
+                    if events.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        ReceiverStream::new(stream)
+    }
+
+    /// Poll all watched values once and get the changes since the previous poll.
+    /// The values are queried within a single batch, thus the poll occupies the connection task only once.
+    async fn poll(&self, state: &mut State) -> Vec<Result<RigEvent, RigError>> {
+        let watched = [self.frequency, self.mode, self.vfo, self.ptt, self.split];
+        let replies = self
+            .handle
+            .call(move |rig| {
+                Box::pin(async move {
+                    let [frequency, mode, vfo, ptt, split] = watched;
+                    let mut batch = rig.batch();
+                    if frequency {
+                        batch = batch.get_frequency();
+                    }
+                    if mode {
+                        batch = batch.get_mode();
+                    }
+                    if vfo {
+                        batch = batch.get_vfo();
+                    }
+                    if ptt {
+                        batch = batch.get_ptt();
+                    }
+                    if split {
+                        batch = batch.get_split_vfo();
+                    }
+                    batch.execute().await
+                })
+            })
+            .await;
+
+        let replies = match replies {
+            Ok(replies) => replies,
+            Err(e) => return vec![Err(e)],
+        };
+
+        let mut events = Vec::new();
+        for reply in replies {
+            match reply {
+                Ok(Reply::Frequency(frequency)) => push_change(
+                    &mut events,
+                    &mut state.frequency,
+                    frequency,
+                    RigEvent::FrequencyChanged,
+                ),
+                Ok(Reply::Mode(mode, passband)) => {
+                    push_change(&mut events, &mut state.mode, (mode, passband), |(m, p)| {
+                        RigEvent::ModeChanged(m, p)
+                    })
+                }
+                Ok(Reply::Vfo(vfo)) => {
+                    push_change(&mut events, &mut state.vfo, vfo, RigEvent::VfoChanged)
+                }
+                Ok(Reply::Ptt(ptt)) => {
+                    push_change(&mut events, &mut state.ptt, ptt, RigEvent::PttChanged)
+                }
+                Ok(Reply::Split(enabled, tx_vfo)) => push_change(
+                    &mut events,
+                    &mut state.split,
+                    (enabled, tx_vfo),
+                    |(enabled, tx_vfo)| RigEvent::SplitChanged { enabled, tx_vfo },
+                ),
+                Ok(_) => events.push(Err(RigError::InternalError)),
+                Err(e) => events.push(Err(e)),
+            }
+        }
+
+        events
+    }
+}
+
+/// Compare a polled value with the previous one and push an event if it changed.
+fn push_change<T, F>(
+    events: &mut Vec<Result<RigEvent, RigError>>,
+    last: &mut Option<T>,
+    value: T,
+    event: F,
+) where
+    T: Copy + PartialEq,
+    F: FnOnce(T) -> RigEvent,
+{
+    if *last != Some(value) {
+        *last = Some(value);
+        events.push(Ok(event(value)));
+    }
+}
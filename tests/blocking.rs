@@ -0,0 +1,30 @@
+#![cfg(feature = "blocking")]
+
+use rigctld::blocking::{Daemon, Rig};
+use rigctld::{Frequency, Mode, Passband, Vfo};
+use std::time::Duration;
+
+#[test]
+fn blocking_rig() {
+    let daemon = Daemon::default();
+    let mut rigctld = daemon.spawn().unwrap();
+
+    let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+    rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+        .unwrap();
+
+    rig.set_frequency(Frequency::from_khz(7074)).unwrap();
+    assert_eq!(rig.get_frequency().unwrap(), Frequency::from_khz(7074));
+    rig.set_mode(Mode::USB, Passband::Hz(2400)).unwrap();
+    assert_eq!(rig.get_mode().unwrap(), (Mode::USB, Passband::Hz(2400)));
+
+    // Overrides apply within the closure only
+    let frequency = rig.with_timeout(Duration::from_secs(1), |rig| rig.get_frequency());
+    assert_eq!(frequency.unwrap(), Frequency::from_khz(7074));
+    let target = rig.with_vfo(Vfo::VFOB, |rig| rig.get_target_vfo());
+    assert_eq!(target, Some(Vfo::VFOB));
+    assert_eq!(rig.get_target_vfo(), None);
+
+    assert!(rig.disconnect());
+    rigctld.kill().unwrap();
+}
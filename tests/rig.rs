@@ -1,6 +1,21 @@
-use rigctld::{Daemon, Mode, Rig};
+#[cfg(feature = "multicast")]
+use rigctld::MulticastListener;
+use rigctld::{
+    BoxFuture, CatTerminator, Clock, CommandClass, ConnectionStatus, Daemon, Frequency, Keepalive,
+    LinkHealth, Mode, Passband, Priority, ReconnectPolicy, Reply, Rig, RigError, RigEvent,
+    RigHandle, RigWatcher, ScanFunction, Throttle, Timeouts, Vfo,
+};
+use std::future::{poll_fn, Future};
+#[cfg(feature = "multicast")]
+use std::net::Ipv4Addr;
+use std::task::Poll;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+#[cfg(feature = "multicast")]
+use tokio::net::UdpSocket;
 use tokio::runtime::Runtime;
-use tokio::time::{sleep, Duration};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::{timeout, Duration};
+use tokio_stream::{Stream, StreamExt};
 
 macro_rules! tokio {
     ($e:expr) => {
@@ -8,19 +23,63 @@ macro_rules! tokio {
     };
 }
 
+/// Spawn a mock of `rigctld` on an in-memory connection, which answers each request by the given reply.
+/// Requests without a reply are never answered.
+/// Returns the client side of the connection and a receiver of the requests,
+/// the mock closes the connection once the receiver is dropped.
+fn mock_rigctld<F, R>(reply: F) -> (DuplexStream, UnboundedReceiver<String>)
+where
+    F: FnMut(&str) -> Option<R> + Send + 'static,
+    R: Into<String>,
+{
+    mock_rigctld_delayed(|_| Duration::ZERO, reply)
+}
+
+/// Spawn a mock of `rigctld` like `mock_rigctld`, which delays the reply to each request by the given delay.
+fn mock_rigctld_delayed<D, F, R>(
+    delay: D,
+    mut reply: F,
+) -> (DuplexStream, UnboundedReceiver<String>)
+where
+    D: Fn(&str) -> Duration + Send + 'static,
+    F: FnMut(&str) -> Option<R> + Send + 'static,
+    R: Into<String>,
+{
+    let (client, server) = tokio::io::duplex(1024);
+    let (requests_tx, requests) = tokio::sync::mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (rx, mut tx) = tokio::io::split(server);
+        let mut lines = BufReader::new(rx).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if requests_tx.send(line.clone()).is_err() {
+                break;
+            }
+            tokio::time::sleep(delay(&line)).await;
+            let response = reply(&line).map(Into::<String>::into);
+            if let Some(response) = response {
+                if tx.write_all(response.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    (client, requests)
+}
+
 #[test]
 fn lifecycle() {
     tokio!({
         let daemon = Daemon::default();
         let mut rigctld = daemon.spawn().await.unwrap();
 
-        sleep(Duration::from_millis(250)).await;
-
-        assert!(rigctld.is_running().unwrap());
-
         let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
-        rig.connect().await.unwrap();
-        assert_eq!(rig.disconnect(), true);
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+        assert!(rigctld.is_running().unwrap());
+        assert!(rig.disconnect());
 
         rigctld.kill().await.unwrap();
     })
@@ -30,7 +89,23 @@ fn lifecycle() {
 fn deamon_not_running() {
     tokio!({
         let mut rig = Rig::new("127.0.0.1", 4532);
-        assert_eq!(rig.connect().await.is_err(), true);
+        assert!(rig.connect().await.is_err());
+    })
+}
+
+#[test]
+fn deamon_exited() {
+    tokio!({
+        let daemon = Daemon::default().set_program("false".into());
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        assert!(matches!(
+            rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+                .await,
+            Err(RigError::DaemonExited(_))
+        ));
+        assert!(!rig.is_connected());
     })
 }
 
@@ -40,43 +115,1049 @@ fn rig_frequency() {
         let daemon = Daemon::default();
         let mut rigctld = daemon.spawn().await.unwrap();
 
-        sleep(Duration::from_millis(250)).await;
-
         let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
-        rig.connect().await.unwrap();
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
 
         let freq_before = rig.get_frequency().await.unwrap();
-        rig.set_frequency(7123000).await.unwrap();
+        rig.set_frequency(Frequency::from_hz(7123000))
+            .await
+            .unwrap();
         let freq_after = rig.get_frequency().await.unwrap();
 
-        assert_ne!(freq_before, 7123000);
-        assert_eq!(freq_after, 7123000);
+        assert_ne!(freq_before, Frequency::from_hz(7123000));
+        assert_eq!(freq_after, Frequency::from_hz(7123000));
 
         rigctld.kill().await.unwrap();
     })
 }
 
 #[test]
-fn rig_mode() {
+fn rig_tuning_step() {
     tokio!({
         let daemon = Daemon::default();
         let mut rigctld = daemon.spawn().await.unwrap();
 
-        sleep(Duration::from_millis(250)).await;
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        rig.set_ts(100).await.unwrap();
+        assert_eq!(rig.get_ts().await.unwrap(), 100);
+
+        rig.set_frequency(Frequency::from_hz(7000040))
+            .await
+            .unwrap();
+        assert_eq!(
+            rig.snap_frequency().await.unwrap(),
+            Frequency::from_hz(7000000)
+        );
+        assert_eq!(rig.step_up().await.unwrap(), Frequency::from_hz(7000100));
+        assert_eq!(
+            rig.step_frequency(-3).await.unwrap(),
+            Frequency::from_hz(6999800)
+        );
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_hz(6999800)
+        );
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_mode() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
 
         let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
-        rig.connect().await.unwrap();
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
 
         let (mode_before, pb_before) = rig.get_mode().await.unwrap();
-        rig.set_mode(Mode::LSB, 1234).await.unwrap();
+        rig.set_mode(Mode::LSB, Passband::Hz(1234)).await.unwrap();
         let (mode_after, pb_after) = rig.get_mode().await.unwrap();
 
         assert_ne!(mode_before, Mode::LSB);
-        assert_ne!(pb_before, 1234);
+        assert_ne!(pb_before, Passband::Hz(1234));
         assert_eq!(mode_after, Mode::LSB);
-        assert_eq!(pb_after, 1234);
+        assert_eq!(pb_after, Passband::Hz(1234));
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_passband() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        // The dummy rig knows a normal (8 kHz) and a narrow (2.4 kHz) filter for AM
+        rig.set_mode(Mode::AM, Passband::Normal).await.unwrap();
+        let (_, pb_normal) = rig.get_mode().await.unwrap();
+        rig.set_mode(Mode::AM, Passband::Narrow).await.unwrap();
+        let (_, pb_narrow) = rig.get_mode().await.unwrap();
+
+        assert_eq!(pb_normal, Passband::Hz(8000));
+        assert_eq!(pb_narrow, Passband::Hz(2400));
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_scan() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        rig.scan(ScanFunction::VFO, 0).await.unwrap();
+        rig.scan(ScanFunction::STOP, 0).await.unwrap();
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_clock() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        let clock: Clock = "2023-08-15T12:34:56.000+0200".parse().unwrap();
+        rig.set_clock(&clock).await.unwrap();
+        let clock_out = rig.get_clock().await.unwrap();
+        assert_eq!(clock_out.to_system_time(), clock.to_system_time());
+
+        rig.sync_clock(0).await.unwrap();
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_rf_power() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        let freq = Frequency::from_khz(14074);
+        let mw = rig.power_to_mw(0.5, freq, &Mode::USB).await.unwrap();
+        let power = rig.mw_to_power(mw, freq, &Mode::USB).await.unwrap();
+        assert!((power - 0.5).abs() < 0.01);
+
+        rig.set_frequency(freq).await.unwrap();
+        rig.set_mode(Mode::USB, Passband::Normal).await.unwrap();
+        rig.set_rf_power_watts(10.0).await.unwrap();
+        let watts = rig.get_rf_power_watts().await.unwrap();
+        assert!((watts - 10.0).abs() < 0.5);
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_lock_twiddle_cache() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        rig.set_lock_mode(true).await.unwrap();
+        assert!(rig.get_lock_mode().await.unwrap());
+        rig.set_lock_mode(false).await.unwrap();
+        assert!(!rig.get_lock_mode().await.unwrap());
+
+        rig.set_twiddle(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(rig.get_twiddle().await.unwrap(), Duration::from_secs(5));
+
+        rig.set_cache(Duration::from_millis(500)).await.unwrap();
+        assert_eq!(rig.get_cache().await.unwrap(), Duration::from_millis(500));
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_conf() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+
+        rig.set_conf("write_delay", "10").await.unwrap();
+        assert_eq!(rig.get_conf("write_delay").await.unwrap(), "10");
+        assert_eq!(
+            rig.set_conf("write_delay", "10 ms").await,
+            Err(RigError::InvalidArgument)
+        );
+
+        let tokens = rig.dump_conf().await.unwrap();
+        let write_delay = tokens.iter().find(|t| t.name == "write_delay").unwrap();
+        assert_eq!(write_delay.value, "10");
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_vfo_mode() {
+    tokio!({
+        let daemon = Daemon::default().set_vfo_mode(true);
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+        assert!(rig.is_vfo_mode());
+        assert_eq!(rig.get_vfo().await.unwrap(), Vfo::VFOA);
+        assert_eq!(
+            rig.batch().get_vfo().execute().await.unwrap(),
+            vec![Ok(Reply::Vfo(Vfo::VFOA))]
+        );
+
+        rig.set_target_vfo(Some(Vfo::VFOA));
+        rig.set_frequency(Frequency::from_khz(7074)).await.unwrap();
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(7074)
+        );
+
+        // Individual commands target another VFO
+        rig.with_vfo(Vfo::VFOB)
+            .set_frequency(Frequency::from_khz(14074))
+            .await
+            .unwrap();
+        assert_eq!(rig.get_target_vfo(), Some(Vfo::VFOA));
+        assert_eq!(
+            rig.with_vfo(Vfo::VFOB).get_frequency().await.unwrap(),
+            Frequency::from_khz(14074)
+        );
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(7074)
+        );
+
+        rig.set_target_vfo(None);
+        rig.set_mode(Mode::USB, Passband::Normal).await.unwrap();
+        assert_eq!(rig.get_mode().await.unwrap().0, Mode::USB);
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_password() {
+    tokio!({
+        let daemon = Daemon::default().set_password("secret".into());
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.set_password(Some("wrong".into()));
+        assert_eq!(
+            rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+                .await,
+            Err(RigError::AuthenticationFailed)
+        );
+        assert!(!rig.is_connected());
+
+        rig.connect_with_password("secret").await.unwrap();
+        rig.get_frequency().await.unwrap();
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_handle() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+        let handle = RigHandle::spawn(rig);
+
+        let tasks: Vec<_> = (0..4u64)
+            .map(|i| {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    handle.set_mode(Mode::USB, Passband::Normal).await.unwrap();
+                    handle
+                        .set_frequency(Frequency::from_khz(7000 + i))
+                        .await
+                        .unwrap();
+                    handle.get_frequency().await.unwrap()
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let freq = handle
+            .call(|rig| Box::pin(rig.get_frequency()))
+            .await
+            .unwrap();
+        assert!(freq >= Frequency::from_khz(7000) && freq < Frequency::from_khz(7004));
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_keepalive() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.set_keepalive(Some(
+            Keepalive::default().set_interval(Duration::from_millis(50)),
+        ));
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+        assert!(rig.get_latency().is_some());
+        assert_eq!(rig.get_consecutive_failures(), 0);
+
+        let mut health = rig.subscribe_health();
+        assert_eq!(*health.borrow_and_update(), LinkHealth::Healthy);
+        let handle = RigHandle::spawn(rig);
+
+        rigctld.kill().await.unwrap();
+        timeout(Duration::from_secs(1), health.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*health.borrow(), LinkHealth::Lost);
+        assert_eq!(handle.get_frequency().await, Err(RigError::NotConnected));
+    })
+}
 
+#[test]
+fn rig_overdue_keepalive() {
+    tokio!({
+        let (client, mut requests) = mock_rigctld(|line| match line {
+            r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+            r";\get_freq" => Some("get_freq:;Frequency: 3573000;RPRT 0\n"),
+            _ => Some("RPRT -4\n"),
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.set_keepalive(Some(
+            Keepalive::default().set_interval(Duration::from_millis(50)),
+        ));
+        rig.connect().await.unwrap();
+        rig.get_frequency().await.unwrap();
+
+        // The keepalive precedes the next command once it is overdue
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        rig.get_frequency().await.unwrap();
+
+        let order: Vec<String> = std::iter::from_fn(|| requests.try_recv().ok()).collect();
+        assert_eq!(
+            order,
+            vec![r";\chk_vfo", r";\get_freq", r";\chk_vfo", r";\get_freq"]
+        );
+    })
+}
+
+#[test]
+fn rig_keepalive_reconnect() {
+    tokio!({
+        let (client, requests) = mock_rigctld(|line| match line {
+            r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+            _ => Some("RPRT -4\n"),
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.set_keepalive(Some(
+            Keepalive::default().set_interval(Duration::from_millis(50)),
+        ));
+        rig.set_reconnect_policy(Some(
+            ReconnectPolicy::default()
+                .set_initial_delay(Duration::from_millis(10))
+                .set_max_attempts(None),
+        ));
+        rig.connect().await.unwrap();
+        let mut status = rig.subscribe_status();
+        let handle = RigHandle::spawn(rig);
+
+        // The connection is lost for good with the first keepalive
+        drop(requests);
+
+        timeout(
+            Duration::from_secs(1),
+            status.wait_for(|s| matches!(s, ConnectionStatus::Reconnecting { attempt: 2 })),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // Commands are executed between the attempts to reconnect
+        let connected = handle
+            .call_with_priority(Priority::High, |rig| {
+                Box::pin(async move { Ok(rig.is_connected()) })
+            })
+            .await;
+        assert_eq!(connected, Ok(false));
+    })
+}
+
+async fn next_event<S>(events: &mut S) -> RigEvent
+where
+    S: Stream<Item = Result<RigEvent, RigError>> + Unpin,
+{
+    timeout(Duration::from_secs(1), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn rig_watcher() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+        assert_eq!(rig.get_vfo().await.unwrap(), Vfo::VFOA);
+        assert!(!rig.get_ptt().await.unwrap());
+        assert_eq!(rig.get_split_vfo().await.unwrap(), (false, Vfo::VFOB));
+        rig.set_frequency(Frequency::from_khz(7074)).await.unwrap();
+        rig.set_mode(Mode::USB, Passband::Hz(2400)).await.unwrap();
+
+        let handle = RigHandle::spawn(rig);
+        let mut events = Box::pin(
+            RigWatcher::new(handle.clone())
+                .set_interval(Duration::from_millis(20))
+                .spawn(),
+        );
+
+        assert_eq!(
+            next_event(&mut events).await,
+            RigEvent::FrequencyChanged(Frequency::from_khz(7074))
+        );
+        assert_eq!(
+            next_event(&mut events).await,
+            RigEvent::ModeChanged(Mode::USB, Passband::Hz(2400))
+        );
+        assert_eq!(
+            next_event(&mut events).await,
+            RigEvent::VfoChanged(Vfo::VFOA)
+        );
+        assert_eq!(next_event(&mut events).await, RigEvent::PttChanged(false));
+        assert_eq!(
+            next_event(&mut events).await,
+            RigEvent::SplitChanged {
+                enabled: false,
+                tx_vfo: Vfo::VFOB
+            }
+        );
+
+        handle
+            .set_frequency(Frequency::from_khz(14074))
+            .await
+            .unwrap();
+        assert_eq!(
+            next_event(&mut events).await,
+            RigEvent::FrequencyChanged(Frequency::from_khz(14074))
+        );
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_connection_lost() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
         rigctld.kill().await.unwrap();
+
+        assert_eq!(rig.get_frequency().await, Err(RigError::ConnectionLost));
+        assert_eq!(rig.get_consecutive_failures(), 1);
+        assert_eq!(rig.get_frequency().await, Err(RigError::NotConnected));
+        assert!(!rig.is_connected());
+    })
+}
+
+#[test]
+fn rig_reconnect() {
+    tokio!({
+        let daemon = Daemon::default();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
+        rig.set_reconnect_policy(Some(
+            ReconnectPolicy::default().set_initial_delay(Duration::from_millis(100)),
+        ));
+        rig.connect_when_ready(Duration::from_secs(2), Some(&mut rigctld))
+            .await
+            .unwrap();
+        let status = rig.subscribe_status();
+        assert_eq!(*status.borrow(), ConnectionStatus::Connected);
+
+        rigctld.kill().await.unwrap();
+        let mut rigctld = daemon.spawn().await.unwrap();
+
+        rig.get_frequency().await.unwrap();
+        assert_eq!(*status.borrow(), ConnectionStatus::Connected);
+
+        rigctld.kill().await.unwrap();
+    })
+}
+
+#[test]
+fn rig_stream_transport() {
+    tokio!({
+        let (client, _requests) = mock_rigctld(|line| match line {
+            r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+            r";\get_freq" => Some("get_freq:;Frequency: 3573000;RPRT 0\n"),
+            _ => Some("RPRT -4\n"),
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(3573)
+        );
+
+        rig.disconnect();
+        assert_eq!(rig.connect().await, Err(RigError::ConnectionError));
+    })
+}
+
+#[test]
+fn rig_timeouts() {
+    tokio!({
+        let (client, _requests) = mock_rigctld_delayed(
+            |line| match line {
+                r";\get_freq" => Duration::from_millis(400),
+                _ => Duration::ZERO,
+            },
+            |line| match line {
+                r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+                r";\get_freq" => Some("get_freq:;Frequency: 3573000;RPRT 0\n"),
+                _ => Some("RPRT -4\n"),
+            },
+        );
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+
+        assert_eq!(
+            rig.with_timeout(Duration::from_secs(1))
+                .get_frequency()
+                .await
+                .unwrap(),
+            Frequency::from_khz(3573)
+        );
+        rig.set_timeouts(
+            Timeouts::default().set_class_timeout(CommandClass::Query, Duration::from_millis(100)),
+        );
+        assert_eq!(
+            rig.get_frequency().await,
+            Err(RigError::CommunicationTimeout)
+        );
+    })
+}
+
+#[test]
+fn rig_handle_timeouts() {
+    tokio!({
+        let (client, _requests) = mock_rigctld_delayed(
+            |line| match line {
+                r";\get_freq" => Duration::from_millis(1200),
+                _ => Duration::ZERO,
+            },
+            |line| match line {
+                r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+                r";\get_freq" => Some("get_freq:;Frequency: 3573000;RPRT 0\n"),
+                _ => Some("RPRT -4\n"),
+            },
+        );
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+        rig.set_timeouts(
+            Timeouts::default().set_class_timeout(CommandClass::Query, Duration::from_secs(2)),
+        );
+
+        // The execution is limited by the timeouts of the rig only
+        let mut handle = RigHandle::spawn(rig);
+        assert_eq!(
+            handle.get_frequency().await.unwrap(),
+            Frequency::from_khz(3573)
+        );
+
+        handle.set_timeout(Duration::from_millis(500));
+        assert_eq!(
+            handle.get_frequency().await,
+            Err(RigError::CommunicationTimeout)
+        );
+    })
+}
+
+#[test]
+fn rig_probe_timeout() {
+    tokio!({
+        // Never answers, e.g. while the rig backend is still starting up
+        let (client, _requests) = mock_rigctld(|_| None::<String>);
+
+        let mut rig = Rig::from_stream(client);
+        assert_eq!(rig.connect().await, Err(RigError::CommunicationTimeout));
+        assert!(!rig.is_connected());
+
+        // An unanswered password is no wrong password
+        let (client, _requests) = mock_rigctld(|_| None::<String>);
+
+        let mut rig = Rig::from_stream(client);
+        assert_eq!(
+            rig.connect_with_password("secret").await,
+            Err(RigError::CommunicationTimeout)
+        );
+        assert!(!rig.is_connected());
+    })
+}
+
+#[test]
+fn rig_cancellation() {
+    tokio!({
+        let mut frequency = String::from("3573000");
+        let (client, _requests) = mock_rigctld_delayed(
+            |line| match line {
+                r";\get_freq" => Duration::from_millis(200),
+                _ => Duration::ZERO,
+            },
+            move |line| match line.split_once(' ') {
+                None if line == r";\chk_vfo" => Some(String::from("chk_vfo:;ChkVFO: 0\nRPRT 0\n")),
+                None if line == r";\get_freq" => {
+                    Some(format!("get_freq:;Frequency: {};RPRT 0\n", frequency))
+                }
+                // Never answered
+                None if line == r";\get_ts" => None,
+                Some((r";\set_freq", value)) => {
+                    frequency = String::from(value);
+                    Some(format!("set_freq: {};RPRT 0\n", value))
+                }
+                _ => Some(String::from("RPRT -4\n")),
+            },
+        );
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+        rig.set_communication_timeout(Duration::from_secs(1));
+
+        // Dropped future, its response is discarded
+        assert!(timeout(Duration::from_millis(50), rig.get_frequency())
+            .await
+            .is_err());
+        rig.set_frequency(Frequency::from_khz(7074)).await.unwrap();
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(7074)
+        );
+
+        // Lost response
+        assert_eq!(
+            rig.with_timeout(Duration::from_millis(50)).get_ts().await,
+            Err(RigError::CommunicationTimeout)
+        );
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(7074)
+        );
+    })
+}
+
+#[test]
+fn rig_lost_response() {
+    tokio!({
+        let mut lost = false;
+        let (client, _requests) = mock_rigctld(move |line| match line {
+            r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+            // The first reply gets lost
+            r";\get_freq" if !lost => {
+                lost = true;
+                None
+            }
+            r";\get_freq" => Some("get_freq:;Frequency: 3573000;RPRT 0\n"),
+            _ => Some("RPRT -4\n"),
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+
+        assert_eq!(
+            rig.get_frequency().await,
+            Err(RigError::CommunicationTimeout)
+        );
+        for _ in 0..3 {
+            assert_eq!(
+                rig.get_frequency().await.unwrap(),
+                Frequency::from_khz(3573)
+            );
+        }
+    })
+}
+
+#[test]
+fn rig_batch() {
+    tokio!({
+        let (client, _requests) = mock_rigctld(|line| match line {
+            r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+            r";\get_freq" => Some("get_freq:;Frequency: 14074000;RPRT 0\n"),
+            r";\get_mode" => Some("get_mode:;Mode: PKTUSB;Passband: 3000;RPRT 0\n"),
+            r";\get_ptt" => Some("get_ptt:;PTT: 0;RPRT 0\n"),
+            r";\get_level STRENGTH" => Some("get_level: STRENGTH;Level Value: -12;RPRT 0\n"),
+            r";\set_freq 7074000" => Some("set_freq: 7074000;RPRT 0\n"),
+            _ => Some("RPRT -11\n"),
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+
+        let replies = rig
+            .batch()
+            .get_frequency()
+            .get_mode()
+            .get_ptt()
+            .get_strength()
+            .get_rf_power()
+            .set_frequency(Frequency::from_khz(7074))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                Ok(Reply::Frequency(Frequency::from_khz(14074))),
+                Ok(Reply::Mode(Mode::PKTUSB, Passband::Hz(3000))),
+                Ok(Reply::Ptt(false)),
+                Ok(Reply::Strength(-12)),
+                Err(RigError::InternalError),
+                Ok(Reply::Done),
+            ]
+        );
+
+        assert_eq!(rig.batch().execute().await.unwrap(), vec![]);
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(14074)
+        );
+    })
+}
+
+#[test]
+fn rig_send_raw_cat() {
+    tokio!({
+        let (client, _requests) = mock_rigctld(|line| {
+            let echo = line[2..].replacen(' ', ": ", 1);
+            match line {
+                r";\chk_vfo" => Some(String::from("chk_vfo:;ChkVFO: 0\nRPRT 0\n")),
+                r";\send_cmd \0xFE\0xFE\0x94\0xE0\0x03\0xFD" => Some(format!(
+                    "{};Reply: 0xfe 0xfe 0xe0 0x94 0xfb 0xfd \n;RPRT 0\n",
+                    echo
+                )),
+                // Text replies are kept even if they look like hex values
+                r";\send_cmd_rx ID; ;" => Some(format!("{};Reply: 0x19;;RPRT 0\n", echo)),
+                _ => Some(String::from("RPRT -11\n")),
+            }
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+
+        assert_eq!(
+            rig.send_raw_cat(
+                &[0xFE, 0xFE, 0x94, 0xE0, 0x03, 0xFD],
+                CatTerminator::Default
+            )
+            .await
+            .unwrap(),
+            vec![0xFE, 0xFE, 0xE0, 0x94, 0xFB, 0xFD]
+        );
+        assert_eq!(
+            rig.send_raw_cat(b"ID;", CatTerminator::Byte(b';'))
+                .await
+                .unwrap(),
+            b"0x19;".to_vec()
+        );
+        assert_eq!(
+            rig.send_raw_cat(b"FA;", CatTerminator::Default).await,
+            Err(RigError::InternalError)
+        );
+    })
+}
+
+#[test]
+fn rig_read_cache() {
+    tokio!({
+        let (client, mut requests) = mock_rigctld(|line| match line {
+            r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+            r";\get_freq" => Some("get_freq:;Frequency: 14074000;RPRT 0\n"),
+            r";\set_freq 7074000" => Some("set_freq: 7074000;RPRT 0\n"),
+            r";\set_mode USB 2400" => Some("set_mode: USB 2400;RPRT 0\n"),
+            _ => Some("RPRT -11\n"),
+        });
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+        rig.set_read_cache(Some(Duration::from_millis(200)));
+        assert_eq!(requests.recv().await.unwrap(), r";\chk_vfo");
+
+        // Served from the cache until expired
+        for _ in 0..3 {
+            assert_eq!(
+                rig.get_frequency().await.unwrap(),
+                Frequency::from_khz(14074)
+            );
+        }
+        assert_eq!(requests.recv().await.unwrap(), r";\get_freq");
+        assert!(requests.try_recv().is_err());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        rig.get_frequency().await.unwrap();
+        assert_eq!(requests.recv().await.unwrap(), r";\get_freq");
+
+        // Updated by set commands
+        rig.set_frequency(Frequency::from_khz(7074)).await.unwrap();
+        rig.set_mode(Mode::USB, Passband::Hz(2400)).await.unwrap();
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(7074)
+        );
+        assert_eq!(
+            rig.get_mode().await.unwrap(),
+            (Mode::USB, Passband::Hz(2400))
+        );
+        assert_eq!(requests.recv().await.unwrap(), r";\set_freq 7074000");
+        assert_eq!(requests.recv().await.unwrap(), r";\set_mode USB 2400");
+        assert!(requests.try_recv().is_err());
+
+        rig.clear_read_cache();
+        assert_eq!(
+            rig.get_frequency().await.unwrap(),
+            Frequency::from_khz(14074)
+        );
+    })
+}
+
+#[test]
+fn rig_throttle() {
+    tokio!({
+        let (client, mut requests) = mock_rigctld_delayed(
+            |line| match line {
+                r";\get_freq" => Duration::from_millis(20),
+                _ => Duration::ZERO,
+            },
+            |line| match line {
+                r";\chk_vfo" => Some("chk_vfo:;ChkVFO: 0\nRPRT 0\n"),
+                r";\get_freq" => Some("get_freq:;Frequency: 14074000;RPRT 0\n"),
+                r";\set_ptt 1" => Some("set_ptt: 1;RPRT 0\n"),
+                _ => Some("RPRT -11\n"),
+            },
+        );
+
+        let mut rig = Rig::from_stream(client);
+        rig.connect().await.unwrap();
+        assert_eq!(requests.recv().await.unwrap(), r";\chk_vfo");
+
+        // Minimum spacing and maximum rate
+        let throttle = Throttle::default()
+            .set_min_spacing(Duration::from_millis(50))
+            .set_max_rate(3, Duration::from_millis(400));
+        rig.set_throttle(Some(throttle.clone()));
+        assert_eq!(rig.get_throttle(), Some(&throttle));
+
+        let start = tokio::time::Instant::now();
+        for _ in 0..3 {
+            rig.get_frequency().await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        rig.get_frequency().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(400));
+
+        for _ in 0..4 {
+            assert_eq!(requests.recv().await.unwrap(), r";\get_freq");
+        }
+
+        // Batches exceeding the maximum rate are split
+        let start = tokio::time::Instant::now();
+        let replies = rig
+            .batch()
+            .get_frequency()
+            .get_frequency()
+            .get_frequency()
+            .get_frequency()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(replies.len(), 4);
+        for reply in replies {
+            assert_eq!(reply, Ok(Reply::Frequency(Frequency::from_khz(14074))));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(400));
+        for _ in 0..4 {
+            assert_eq!(requests.recv().await.unwrap(), r";\get_freq");
+        }
+
+        // Commands changing the rig overtake queued queries
+        rig.set_throttle(Some(
+            Throttle::default().set_min_spacing(Duration::from_millis(50)),
+        ));
+        let handle = RigHandle::spawn(rig);
+
+        // Block the connection task until all commands are queued
+        let (started_tx, started) = tokio::sync::oneshot::channel();
+        let (release, release_rx) = tokio::sync::oneshot::channel::<()>();
+        let gate = handle.clone();
+        let gate = tokio::spawn(async move {
+            gate.call(move |rig| {
+                Box::pin(async move {
+                    let frequency = rig.get_frequency().await;
+                    started_tx.send(()).unwrap();
+                    let _ = release_rx.await;
+                    frequency
+                })
+            })
+            .await
+        });
+        started.await.unwrap();
+
+        let mut polls: Vec<BoxFuture<Result<Frequency, RigError>>> = (0..4)
+            .map(|_| {
+                let handle = handle.clone();
+                Box::pin(async move { handle.get_frequency().await }) as _
+            })
+            .collect();
+        let mut ptt = Box::pin(handle.set_ptt(true));
+        poll_fn(|cx| {
+            for poll in polls.iter_mut() {
+                assert!(poll.as_mut().poll(cx).is_pending());
+            }
+            assert!(ptt.as_mut().poll(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+        release.send(()).unwrap();
+
+        gate.await.unwrap().unwrap();
+        ptt.await.unwrap();
+        for poll in polls {
+            poll.await.unwrap();
+        }
+
+        let order: Vec<String> = std::iter::from_fn(|| requests.try_recv().ok()).collect();
+        assert_eq!(order.len(), 6);
+        assert_eq!(order[1], r";\set_ptt 1");
+
+        // Commands whose caller gave up within the queue are not sent
+        let mut impatient = handle.clone();
+        impatient.set_queue_timeout(Duration::from_millis(10));
+        let polls: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = impatient.clone();
+                tokio::spawn(async move { handle.get_frequency().await })
+            })
+            .collect();
+        let mut timeouts = 0;
+        for poll in polls {
+            if poll.await.unwrap() == Err(RigError::CommunicationTimeout) {
+                timeouts += 1;
+            }
+        }
+        assert_eq!(timeouts, 3);
+        handle.get_frequency().await.unwrap();
+
+        let order: Vec<String> = std::iter::from_fn(|| requests.try_recv().ok()).collect();
+        assert_eq!(order, vec![r";\get_freq"; 2]);
+    })
+}
+
+#[test]
+#[cfg(feature = "multicast")]
+fn multicast_listener() {
+    tokio!({
+        let group = Ipv4Addr::new(239, 255, 0, 1);
+        let mut listener = MulticastListener::bind(group, 0).unwrap();
+        let port = listener.get_port();
+        let commands = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        listener.set_command_addr(commands.local_addr().unwrap());
+
+        let sender = UdpSocket::bind("0.0.0.0:0").await.unwrap();
+        let packet = r#"{"app": "Hamlib", "seq": 7,
+            "rig": {"name": "Dummy", "errorMsg": "", "split": false, "splitVfo": "VFOB", "satMode": false},
+            "vfos": [{"name": "VFOA", "freq": 7074000, "mode": "PKTUSB", "width": 3000, "ptt": true, "rx": true, "tx": true}]}"#;
+        sender.send_to(b"garbage", (group, port)).await.unwrap();
+        sender
+            .send_to(packet.as_bytes(), (group, port))
+            .await
+            .unwrap();
+
+        assert_eq!(listener.recv().await, Err(RigError::ParseError));
+        let state = timeout(Duration::from_secs(1), listener.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.seq, 7);
+        assert_eq!(state.vfos[0].frequency, Frequency::from_khz(7074));
+        assert_eq!(state.vfos[0].mode, Some(Mode::PKTUSB));
+        assert!(state.vfos[0].ptt);
+
+        listener.send_command(r"\set_ptt 0").await.unwrap();
+        let mut buffer = [0; 64];
+        let len = commands.recv(&mut buffer).await.unwrap();
+        assert_eq!(&buffer[..len], b"\\set_ptt 0\n");
     })
 }
 
@@ -91,10 +1172,10 @@ fn device_icom_ic7200() {
             .set_rig_file("/dev/ttyUSB0".into());
         let mut rigctld = daemon.spawn().await.unwrap();
 
-        sleep(Duration::from_millis(1000)).await;
-
         let mut rig = Rig::new(daemon.get_host(), daemon.get_port());
-        rig.connect().await.unwrap();
+        rig.connect_when_ready(Duration::from_secs(5), Some(&mut rigctld))
+            .await
+            .unwrap();
 
         rig.get_frequency().await.unwrap();
         rig.get_mode().await.unwrap();